@@ -1,38 +1,733 @@
 //! Absences
 
-use crate::{time::MyDate, user::User, utils};
-use ekreta::{Absence, Res};
+use crate::{config::CONFIG, time::MyDate, timetable::given_on, user::User, utils};
+use chrono::{Datelike, Local, Months, NaiveDate, Weekday};
+use ekreta::{Absence, AnnouncedTest, HomeWork, LDateTime, Lesson, Res};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use yansi::{Color, Paint};
 
-pub fn handle(user: &User, subj: Option<String>, count: bool, args: &crate::Args) -> Res<()> {
+/// what to show about the absences
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum View {
+    List,
+    Count,
+    Summary,
+    Lateness,
+    ByDay,
+    CatchUp,
+    Calendar,
+}
+
+/// length of a lesson, accumulated lateness counts as an hour of absence after this many minutes
+const LESSON_MINS: u32 = 45;
+
+pub fn handle(user: &User, subj: Option<String>, view: View, args: &crate::Args) -> Res<()> {
     let mut absences = user.get_absences((None, None))?;
-    if let Some(subject) = subj {
-        filter_by_subject(&mut absences, &subject);
+    if let Some(subject) = subj.as_ref() {
+        filter_by_subject(&mut absences, subject);
     }
-    if count {
-        let unver = absences.iter().filter(|item| !item.igazolt()).count();
-        if args.machine {
-            println!("{{\"verified\":{},\"unverified\":{unver}}}", absences.len());
-        } else {
-            println!("Összes hiányzásod száma: {}", absences.len());
-            println!("Ebből még igazolatlan: {unver}");
+    match view {
+        View::Count => print_counts(&count(&absences), args),
+        View::Summary => {
+            let mut lessons = user.get_cached_timetable();
+            if let Some(subject) = subj.as_ref() {
+                lessons.retain(|lsn| {
+                    lsn.tantargy
+                        .as_ref()
+                        .is_some_and(|s| subj_matches(&s.nev, subject))
+                });
+            }
+            print_summary(&summarize(&absences, &lessons), args)
+        }
+        View::Lateness => print_lateness(&lateness(&absences), args),
+        View::ByDay => {
+            let days = by_day(&absences, &user.get_cached_timetable());
+            let headers = ["NAP", "ÓRÁK", "TANTÁRGYAK", "IGAZOLVA", "IGAZOLÁS TÍPUS"].into_iter();
+            let disp = if args.machine {
+                None
+            } else {
+                Some(display_day)
+            };
+            utils::print_table(&days, headers, args.reverse, args.number, disp)
+        }
+        View::CatchUp => {
+            let lessons = user.get_cached_timetable();
+            let tests = user.get_tests((None, None)).unwrap_or_default();
+            let homework = user.get_homework((None, None)).unwrap_or_default();
+            let missed = catch_up(&absences, &lessons, &tests, &homework);
+            let headers = ["EKKOR", "TANTÁRGY", "TÉMA", "SZÁMONKÉRÉS", "HÁZI"].into_iter();
+            let disp = if args.machine {
+                None
+            } else {
+                Some(display_missed)
+            };
+            utils::print_table(&missed, headers, args.reverse, args.number, disp)
+        }
+        View::Calendar => {
+            let lessons = user.get_cached_timetable();
+            let calendar = calendar(&absences, &lessons);
+            if args.machine {
+                utils::print_json(&calendar)?;
+            } else {
+                print_calendar(&calendar);
+            }
+            Ok(())
+        }
+        View::List => {
+            #[rustfmt::skip]
+            let headers = ["TANTÁRGY", "TANÁR", "ETTŐL", "EDDIG", "ENNYIT", "IGAZOLÁS TÍPUS"].into_iter();
+            let disp = if args.machine { None } else { Some(display) };
+            utils::print_table(&absences, headers, args.reverse, args.number, disp)
         }
-        return Ok(());
     }
-    #[rustfmt::skip]
-    let headers = ["TANTÁRGY", "TANÁR", "ETTŐL", "EDDIG", "ENNYIT", "IGAZOLÁS TÍPUS"].into_iter();
-    let disp = if args.machine { None } else { Some(display) };
-    utils::print_table(&absences, headers, args.reverse, args.number, disp)
 }
 
 /// filter [`Abs`]ences by `subj`ect
 pub fn filter_by_subject(abss: &mut Vec<Absence>, subj: &str) {
     log::info!("filtering absences by subject: {subj}");
-    abss.retain(|abs| {
-        abs.tantargy
-            .nev
-            .to_lowercase()
-            .contains(&subj.to_lowercase())
+    abss.retain(|abs| subj_matches(&abs.tantargy.nev, subj));
+}
+
+/// whether subject `name` matches `filter`
+fn subj_matches(name: &str, filter: &str) -> bool {
+    name.to_lowercase().contains(&filter.to_lowercase())
+}
+
+/// the cached [`Lesson`] `abs` happened at, preferably of the same subject
+fn lesson_of<'a>(abs: &Absence, lessons: &'a [Lesson]) -> Option<&'a Lesson> {
+    let same_time = |lsn: &&Lesson| lsn.kezdet_idopont == abs.ora.kezdo_datum && !lsn.kamu_smafu();
+    let same_subj = |lsn: &&Lesson| {
+        lsn.tantargy
+            .as_ref()
+            .is_some_and(|s| s.nev == abs.tantargy.nev)
+    };
+    let mut at_that_time = lessons.iter().filter(same_time);
+    at_that_time
+        .clone()
+        .find(same_subj)
+        .or_else(|| at_that_time.next())
+}
+
+/// number of absences by state
+#[derive(Debug, Default, Serialize)]
+struct Counts {
+    total: usize,
+    justified: usize,
+    unjustified: usize,
+    /// waiting for the justification to be accepted
+    pending: usize,
+    late: usize,
+    late_minutes: u32,
+}
+impl Counts {
+    fn add(&mut self, abs: &Absence) {
+        self.total += 1;
+        if abs.igazolt() {
+            self.justified += 1;
+        } else if abs.igazolas_allapota.eq_ignore_ascii_case("igazolando") {
+            self.pending += 1;
+        } else {
+            self.unjustified += 1;
+        }
+        if let Some(mins) = abs.keses_percben {
+            self.late += 1;
+            self.late_minutes += mins;
+        }
+    }
+}
+
+/// [`Counts`] of all absences and of each subject
+#[derive(Debug, Default, Serialize)]
+struct CountSummary {
+    #[serde(flatten)]
+    all: Counts,
+    subjects: BTreeMap<String, Counts>,
+}
+
+fn count(absences: &[Absence]) -> CountSummary {
+    let mut summary = CountSummary::default();
+    for abs in absences {
+        summary.all.add(abs);
+        let subject = abs.tantargy.nev.clone();
+        summary.subjects.entry(subject).or_default().add(abs);
+    }
+    summary
+}
+
+fn print_counts(summary: &CountSummary, args: &crate::Args) -> Res<()> {
+    if args.machine {
+        utils::print_json(summary)?;
+        return Ok(());
+    }
+    let all = &summary.all;
+    println!("Összes hiányzásod száma: {}", all.total);
+    println!("Ebből igazolt: {}", all.justified);
+    println!("Ebből még igazolatlan: {}", all.unjustified);
+    println!("Ebből igazolásra vár: {}", all.pending);
+    println!(
+        "Késéseid száma: {}, összesen {} perc",
+        all.late, all.late_minutes
+    );
+
+    let data = summary.subjects.iter().collect::<Vec<_>>();
+    let disp = |(subject, counts): &(&String, &Counts)| {
+        let nums = [
+            counts.total,
+            counts.justified,
+            counts.unjustified,
+            counts.pending,
+        ];
+        let mut row = vec![subject.to_string()];
+        row.extend(nums.map(|n| n.to_string()));
+        row.push(format!("{} ({} perc)", counts.late, counts.late_minutes));
+        row
+    };
+    #[rustfmt::skip]
+    let headers = ["TANTÁRGY", "ÖSSZES", "IGAZOLT", "IGAZOLATLAN", "IGAZOLÁSRA VÁR", "KÉSÉS"].into_iter();
+    utils::print_table(&data, headers, args.reverse, args.number, Some(disp))
+}
+
+/// missed lessons of a subject, compared to the ones held so far
+#[derive(Debug, Default, Serialize)]
+struct SubjSummary {
+    subject: String,
+    /// lessons held so far, according to the cached timetable
+    held: usize,
+    /// missed ones of the `held` lessons
+    missed: usize,
+    justified: usize,
+    unjustified: usize,
+    missed_percent: Option<f32>,
+}
+
+/// per-subject and total numbers of missed lessons, against the limits
+#[derive(Debug, Serialize)]
+struct Summary {
+    subjects: Vec<SubjSummary>,
+    /// all missed lessons, even if not found in the cached timetable
+    total_missed: usize,
+    /// missed lessons that aren't in the cached timetable, so aren't in `subjects`
+    not_cached: usize,
+    limits: crate::config::AbsenceLimits,
+}
+
+fn summarize(absences: &[Absence], lessons: &[Lesson]) -> Summary {
+    let now = Local::now();
+    let mut subjects = BTreeMap::<String, SubjSummary>::new();
+    let held = lessons
+        .iter()
+        .filter(|lsn| lsn.kezdet_idopont <= now && !lsn.kamu_smafu() && !lsn.cancelled());
+    for lsn in held {
+        if let Some(subj) = lsn.tantargy.as_ref() {
+            subjects.entry(subj.nev.clone()).or_default().held += 1;
+        }
+    }
+    // being late isn't missing the lesson
+    let missed = absences.iter().filter(|abs| abs.keses_percben.is_none());
+    let mut not_cached = 0;
+    for abs in missed.clone() {
+        let Some(subj) = lesson_of(abs, lessons).and_then(|lsn| lsn.tantargy.as_ref()) else {
+            not_cached += 1;
+            continue;
+        };
+        let summary = subjects.entry(subj.nev.clone()).or_default();
+        summary.missed += 1;
+        if abs.igazolt() {
+            summary.justified += 1;
+        } else {
+            summary.unjustified += 1;
+        }
+    }
+    let subjects = subjects.into_iter().map(|(subject, mut summary)| {
+        summary.subject = subject;
+        summary.missed_percent =
+            (summary.held > 0).then(|| summary.missed as f32 / summary.held as f32 * 100.);
+        summary
+    });
+    Summary {
+        subjects: subjects.collect(),
+        total_missed: missed.count(),
+        not_cached,
+        limits: CONFIG.absence_limits.clone(),
+    }
+}
+
+/// `text` painted red if `value` reached `limit`, yellow if it's getting close
+fn paint_by_limit(text: String, value: f32, limit: f32) -> String {
+    if value >= limit {
+        text.red().bold().to_string()
+    } else if value >= limit * CONFIG.absence_limits.warn_at {
+        text.yellow().to_string()
+    } else {
+        text
+    }
+}
+
+fn print_summary(summary: &Summary, args: &crate::Args) -> Res<()> {
+    if args.machine {
+        utils::print_json(summary)?;
+        return Ok(());
+    }
+    let limit = summary.limits.subject_percent;
+    let disp = |subj: &SubjSummary| {
+        let percent = subj.missed_percent.unwrap_or_default();
+        let percent_s = subj
+            .missed_percent
+            .map(|p| format!("{p:.1}%"))
+            .unwrap_or_default();
+        let nums = [subj.held, subj.missed, subj.justified, subj.unjustified];
+        let mut row = vec![subj.subject.clone()];
+        row.extend(nums.map(|n| n.to_string()));
+        row.push(paint_by_limit(percent_s, percent, limit));
+        row
+    };
+    #[rustfmt::skip]
+    let headers = ["TANTÁRGY", "MEGTARTOTT", "HIÁNYZOTT", "IGAZOLT", "IGAZOLATLAN", "ARÁNY"].into_iter();
+    utils::print_table(
+        &summary.subjects,
+        headers,
+        args.reverse,
+        args.number,
+        Some(disp),
+    )?;
+
+    let (total, max) = (summary.total_missed, summary.limits.total);
+    let total_s = format!("Összesen {total} órát hiányoztál, a megengedett {max}-ból.");
+    println!("{}", paint_by_limit(total_s, total as f32, max as f32));
+    for subj in &summary.subjects {
+        if let Some(percent) = subj.missed_percent
+            && percent >= limit * summary.limits.warn_at
+        {
+            let warning = format!(
+                "{}: az órák {percent:.1}%-át hiányoztad, a határ {limit}%",
+                subj.subject
+            );
+            println!("{}", paint_by_limit(warning, percent, limit));
+        }
+    }
+    if summary.not_cached > 0 {
+        let n = summary.not_cached;
+        println!("{n} hiányzott óra nincs az órarend cache-ben, így az arányokba nem számít bele.");
+    }
+    Ok(())
+}
+
+/// late arrivals summed up
+#[derive(Debug, Default, Serialize)]
+struct LateSum {
+    /// subject or month
+    name: String,
+    count: usize,
+    minutes: u32,
+    /// lessons worth of lateness
+    hours: f32,
+}
+impl LateSum {
+    fn add(&mut self, mins: u32) {
+        self.count += 1;
+        self.minutes += mins;
+        self.hours = self.minutes as f32 / LESSON_MINS as f32;
+    }
+}
+
+/// late arrivals, summed up by subject and by month
+#[derive(Debug, Serialize)]
+struct Lateness<'a> {
+    events: Vec<&'a Absence>,
+    by_subject: Vec<LateSum>,
+    by_month: Vec<LateSum>,
+    total: LateSum,
+}
+
+fn lateness(absences: &[Absence]) -> Lateness<'_> {
+    let mut by_subject = BTreeMap::<String, LateSum>::new();
+    let mut by_month = BTreeMap::<String, LateSum>::new();
+    let mut total = LateSum::default();
+    let mut events = Vec::new();
+    for abs in absences {
+        let Some(mins) = abs.keses_percben else {
+            continue;
+        };
+        let month = abs.ora.kezdo_datum.format("%Y-%m").to_string();
+        by_subject
+            .entry(abs.tantargy.nev.clone())
+            .or_default()
+            .add(mins);
+        by_month.entry(month).or_default().add(mins);
+        total.add(mins);
+        events.push(abs);
+    }
+    let named = |(name, sum): (String, LateSum)| LateSum { name, ..sum };
+    Lateness {
+        events,
+        by_subject: by_subject.into_iter().map(named).collect(),
+        by_month: by_month.into_iter().map(named).collect(),
+        total,
+    }
+}
+
+fn print_lateness(lateness: &Lateness, args: &crate::Args) -> Res<()> {
+    if args.machine {
+        utils::print_json(lateness)?;
+        return Ok(());
+    }
+    let disp_event = |abs: &&Absence| {
+        let when = abs.ora.kezdo_datum.pretty();
+        let mins = abs.keses_percben.unwrap_or_default().to_string();
+        let state = abs.igazolas_allapota.to_lowercase().replace("do", "dó");
+        vec![
+            abs.tantargy.nev.clone(),
+            abs.rogzito_tanar_neve.clone(),
+            when,
+            mins,
+            state,
+        ]
+    };
+    let headers = ["TANTÁRGY", "TANÁR", "EKKOR", "PERC", "IGAZOLÁS"].into_iter();
+    let (rev, num) = (args.reverse, args.number);
+    utils::print_table(&lateness.events, headers, rev, num, Some(disp_event))?;
+
+    let disp_sum = |sum: &LateSum| {
+        let nums = [sum.count.to_string(), sum.minutes.to_string()];
+        let mut row = vec![sum.name.clone()];
+        row.extend(nums);
+        row.push(format!("{:.2}", sum.hours));
+        row
+    };
+    let headers = ["TANTÁRGY", "KÉSÉS", "PERC", "ÓRA"].into_iter();
+    utils::print_table(
+        &lateness.by_subject,
+        headers,
+        false,
+        usize::MAX,
+        Some(disp_sum),
+    )?;
+    let headers = ["HÓNAP", "KÉSÉS", "PERC", "ÓRA"].into_iter();
+    utils::print_table(
+        &lateness.by_month,
+        headers,
+        false,
+        usize::MAX,
+        Some(disp_sum),
+    )?;
+
+    let total = &lateness.total;
+    let hours = format!("{:.2} órának", total.hours);
+    println!(
+        "Összesen {} percet késtél, ez {} felel meg.",
+        total.minutes,
+        hours.bold()
+    );
+    Ok(())
+}
+
+/// how much of something is justified
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Justified {
+    All,
+    Some,
+    None,
+}
+
+/// lessons missed on a day, the unit of justification
+#[derive(Debug, Serialize)]
+struct Day {
+    date: NaiveDate,
+    from: LDateTime,
+    to: LDateTime,
+    /// period of the first missed lesson, if found in the cached timetable
+    first_period: Option<u8>,
+    /// period of the last missed lesson, if found in the cached timetable
+    last_period: Option<u8>,
+    /// number of lessons missed
+    missed: usize,
+    subjects: Vec<String>,
+    justified: Justified,
+    /// kinds of justification, eg. "Orvosi igazolás"
+    justification_kinds: Vec<String>,
+}
+
+/// collapse `absences` into one [`Day`] each, leaving out late arrivals
+fn by_day(absences: &[Absence], lessons: &[Lesson]) -> Vec<Day> {
+    let mut days = BTreeMap::<NaiveDate, Vec<&Absence>>::new();
+    for abs in absences.iter().filter(|abs| abs.keses_percben.is_none()) {
+        let date = abs.ora.kezdo_datum.date_naive();
+        days.entry(date).or_default().push(abs);
+    }
+    let to_day = |(date, mut missed): (NaiveDate, Vec<&Absence>)| {
+        missed.sort_by_key(|abs| abs.ora.kezdo_datum);
+        let (first, last) = (missed.first()?, missed.last()?);
+        let period = |abs: &Absence| lesson_of(abs, lessons).and_then(|lsn| lsn.oraszam);
+        let mut subjects = Vec::<String>::new();
+        let mut justification_kinds = Vec::<String>::new();
+        for abs in &missed {
+            if !subjects.contains(&abs.tantargy.nev) {
+                subjects.push(abs.tantargy.nev.clone());
+            }
+            if let Some(kind) = abs.igazolas_tipusa.as_ref()
+                && !justification_kinds.contains(&kind.leiras)
+            {
+                justification_kinds.push(kind.leiras.clone());
+            }
+        }
+        let n_justified = missed.iter().filter(|abs| abs.igazolt()).count();
+        let justified = match n_justified {
+            0 => Justified::None,
+            n if n == missed.len() => Justified::All,
+            _ => Justified::Some,
+        };
+        Some(Day {
+            date,
+            from: first.ora.kezdo_datum,
+            to: last.ora.veg_datum,
+            first_period: period(first),
+            last_period: period(last),
+            missed: missed.len(),
+            subjects,
+            justified,
+            justification_kinds,
+        })
+    };
+    days.into_iter().filter_map(to_day).collect()
+}
+
+fn display_day(day: &Day) -> Vec<String> {
+    let date = format!("{}, {}", day.date.pretty(), day.date.hun_day_of_week());
+    let periods = if let (Some(first), Some(last)) = (day.first_period, day.last_period) {
+        format!("{first}. - {last}.")
+    } else {
+        let (from, to) = (day.from.format("%H:%M"), day.to.format("%H:%M"));
+        format!("{from} - {to}")
+    };
+    let justified = match day.justified {
+        Justified::All => "mind".green(),
+        Justified::Some => "részben".yellow(),
+        Justified::None => "nem".red(),
+    }
+    .to_string();
+    let kinds = day.justification_kinds.join(", ");
+
+    vec![date, periods, day.subjects.join(", "), justified, kinds]
+}
+
+/// missed days, with the number of lessons missed on each weekday and period
+#[derive(Debug, Serialize)]
+struct Calendar {
+    days: Vec<Day>,
+    /// monday first
+    weekdays: [usize; 7],
+    /// by period number
+    periods: BTreeMap<u8, usize>,
+    /// lessons that aren't in the cached timetable, so their period is unknown
+    unknown_period: usize,
+}
+
+fn calendar(absences: &[Absence], lessons: &[Lesson]) -> Calendar {
+    let days = by_day(absences, lessons);
+    let mut weekdays = [0; 7];
+    for day in &days {
+        weekdays[day.date.weekday().num_days_from_monday() as usize] += day.missed;
+    }
+    let mut periods = BTreeMap::new();
+    let mut unknown_period = 0;
+    for abs in absences.iter().filter(|abs| abs.keses_percben.is_none()) {
+        if let Some(period) = lesson_of(abs, lessons).and_then(|lsn| lsn.oraszam) {
+            *periods.entry(period).or_default() += 1;
+        } else {
+            unknown_period += 1;
+        }
+    }
+    Calendar {
+        days,
+        weekdays,
+        periods,
+        unknown_period,
+    }
+}
+
+/// missed lessons needed on a day for each shade on the calendar, darkest first
+const SHADE_FROM: [usize; 4] = [1, 2, 4, 6];
+const WEEKDAYS: [&str; 7] = ["H", "K", "Sze", "Cs", "P", "Szo", "V"];
+
+/// month by month, from the start of the school year or the first absence, whichever is earlier
+fn print_calendar(calendar: &Calendar) {
+    let today = Local::now().date_naive();
+    let sept = if today.month() >= 9 {
+        today.year()
+    } else {
+        today.year() - 1
+    };
+    let year_start = NaiveDate::from_ymd_opt(sept, 9, 1).unwrap_or(today);
+    let first = calendar
+        .days
+        .first()
+        .map_or(year_start, |d| d.date.min(year_start));
+    let last = calendar.days.last().map_or(today, |d| d.date.max(today));
+    let days = calendar.days.iter().map(|d| (d.date, d)).collect();
+
+    let mut month = first.with_day(1).unwrap_or(first);
+    while month <= last {
+        print_month(month, &days);
+        let Some(next) = month.checked_add_months(Months::new(1)) else {
+            break;
+        };
+        month = next;
+    }
+    let shades = SHADE_FROM.iter().enumerate().map(|(level, from)| {
+        let sample = format!(" {from}+ ");
+        sample.bg(shade(Justified::None, level)).to_string()
     });
+    let legend = [
+        "+ igazolt".green().to_string(),
+        "~ részben igazolt".yellow().to_string(),
+        "! igazolatlan".red().to_string(),
+        format!("{} óra", shades.collect::<String>()),
+    ];
+    println!("{}\n", legend.join(", "));
+
+    let weekdays = calendar.weekdays.map(|n| n.to_string()).to_vec();
+    utils::print_table_wh(WEEKDAYS, vec![weekdays]);
+    let mut periods = calendar
+        .periods
+        .keys()
+        .map(|p| format!("{p}."))
+        .collect::<Vec<_>>();
+    let mut per_period = calendar
+        .periods
+        .values()
+        .map(usize::to_string)
+        .collect::<Vec<_>>();
+    if calendar.unknown_period > 0 {
+        periods.push(String::from("?"));
+        per_period.push(calendar.unknown_period.to_string());
+    }
+    utils::print_table_wh(periods, vec![per_period]);
+}
+
+fn print_month(month: NaiveDate, days: &BTreeMap<NaiveDate, &Day>) {
+    println!(
+        "{}",
+        format!("{}. {}", month.year(), month.hun_month()).bold()
+    );
+    println!("{}", WEEKDAYS.map(|d| format!("{d:>3} ")).concat());
+    let offset = month.weekday().num_days_from_monday() as usize;
+    let mut line = "    ".repeat(offset);
+    for date in month.iter_days().take_while(|d| d.month() == month.month()) {
+        line.push_str(&calendar_cell(date, days.get(&date)));
+        if date.weekday() == Weekday::Sun {
+            println!("{}", line.trim_end());
+            line = String::new(); // `clear()` would resolve to `yansi::Paint::clear`
+        }
+    }
+    if !line.is_empty() {
+        println!("{}", line.trim_end());
+    }
+    println!();
+}
+
+/// day of month, coloured and marked by how many lessons were missed and whether they're justified
+fn calendar_cell(date: NaiveDate, day: Option<&&Day>) -> String {
+    let Some(day) = day else {
+        let cell = format!("{:>3} ", date.day());
+        let weekend = date.weekday().num_days_from_monday() >= 5;
+        return if weekend {
+            cell.dim().to_string()
+        } else {
+            cell
+        };
+    };
+    let marker = match day.justified {
+        Justified::All => '+',
+        Justified::Some => '~',
+        Justified::None => '!',
+    };
+    let level = SHADE_FROM.iter().rposition(|&from| day.missed >= from);
+    let level = level.unwrap_or_default();
+    let text = if level < 2 {
+        Color::White
+    } else {
+        Color::Black
+    };
+    let cell = format!("{:>3}{marker}", date.day());
+    cell.bg(shade(day.justified, level)).fg(text).to_string()
+}
+
+/// background of a day by its `justified` state, lighter for a higher `level` of [`SHADE_FROM`]
+fn shade(justified: Justified, level: usize) -> Color {
+    // 256-colour codes from dark to light
+    let ramp: [u8; 4] = match justified {
+        Justified::All => [22, 28, 34, 40],
+        Justified::Some => [58, 100, 142, 184],
+        Justified::None => [52, 88, 124, 160],
+    };
+    Color::Fixed(ramp[level.min(ramp.len() - 1)])
+}
+
+/// what happened on a missed lesson
+#[derive(Debug, Serialize)]
+struct Missed<'a> {
+    absence: &'a Absence,
+    /// if found in the cached timetable
+    lesson: Option<&'a Lesson>,
+    /// announced test written on that lesson
+    test: Option<&'a AnnouncedTest>,
+    /// homework given on that lesson
+    homework: Option<&'a HomeWork>,
+}
+
+/// look up everything missed on the lessons of `absences`, leaving out late arrivals
+fn catch_up<'a>(
+    absences: &'a [Absence],
+    lessons: &'a [Lesson],
+    tests: &'a [AnnouncedTest],
+    homework: &'a [HomeWork],
+) -> Vec<Missed<'a>> {
+    let missed = absences.iter().filter(|abs| abs.keses_percben.is_none());
+    let to_missed = |absence| {
+        let lesson = lesson_of(absence, lessons);
+        let test = lesson.and_then(|lsn| {
+            let on_lesson = |test: &&AnnouncedTest| {
+                lsn.bejelentett_szamonkeres_uid.as_ref() == Some(&test.uid)
+                    || (test.datum.date_naive() == lsn.date_naive()
+                        && test.orarendi_ora_oraszama == lsn.oraszam)
+            };
+            tests.iter().find(on_lesson)
+        });
+        let homework = lesson.and_then(|lsn| homework.iter().find(|hw| given_on(hw, lsn)));
+        Missed {
+            absence,
+            lesson,
+            test,
+            homework,
+        }
+    };
+    missed.map(to_missed).collect()
+}
+
+fn display_missed(missed: &Missed) -> Vec<String> {
+    let when = missed.absence.ora.kezdo_datum.pretty();
+    let subj = missed.absence.tantargy.nev.clone();
+    let topic = if let Some(lsn) = missed.lesson {
+        lsn.tema.clone().unwrap_or_default()
+    } else {
+        "nincs az órarend cache-ben".dim().italic().to_string()
+    };
+    let test = missed.test.map(|test| {
+        let topic = test.temaja.as_ref().map(|t| format!(": {}", t.italic()));
+        format!("{}{}", test.modja.leiras.bold(), topic.unwrap_or_default())
+    });
+
+    let homework = missed.homework.map(crate::homework::task);
+
+    vec![
+        when,
+        subj,
+        topic,
+        test.unwrap_or_default(),
+        homework.unwrap_or_default(),
+    ]
 }
 
 fn display(abs: &Absence) -> Vec<String> {
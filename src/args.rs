@@ -1,8 +1,11 @@
 //! CLI arguments
 
+use crate::messages::ExportFormat;
+use chrono::{NaiveDate, TimeDelta};
 use clap::{Parser, Subcommand};
-use log::{LevelFilter, info};
+use log::{LevelFilter, info, warn};
 use std::io::{self, IsTerminal};
+use std::sync::OnceLock;
 
 /// default number of entries to show
 const NUM: usize = usize::MAX;
@@ -33,6 +36,58 @@ pub struct Args {
     /// Manually set a user (by name or ID) for a command
     #[arg(long, env = "RSFILC_USER")]
     pub user: Option<String>,
+    /// read the account password from the first line of stdin, instead of asking for it
+    #[arg(long, default_value_t = false)]
+    pub password_stdin: bool,
+    /// never prompt, exit with code 3 instead, the password may come from `RSFILC_PASSWORD_COMMAND`
+    #[arg(long, default_value_t = false, env = "RSFILC_NON_INTERACTIVE")]
+    pub non_interactive: bool,
+    /// use cached data of any kind up to this age without reaching out to the server, eg. 30s, 10m, 2h, 1d
+    #[arg(long, value_name = "AGE", value_parser = crate::time::parse_age)]
+    pub max_age: Option<TimeDelta>,
+    /// never reach out to the server, only show what's cached
+    #[arg(long, default_value_t = false, env = "NO_NET")]
+    pub offline: bool,
+}
+
+/// exit code when something would need to be asked, but `--non-interactive` was given
+pub const NEEDS_INTERACTION_EXIT: i32 = 3;
+/// something would need to be asked, but `--non-interactive` was given
+#[derive(Debug)]
+pub struct NeedsInteraction(pub &'static str);
+impl std::fmt::Display for NeedsInteraction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} needed, but running non-interactively", self.0)
+    }
+}
+impl std::error::Error for NeedsInteraction {}
+
+/// the parts of [`Args`] needed deep down, eg. when a token is needed
+#[derive(Debug, Default)]
+pub struct Opts {
+    pub password_stdin: bool,
+    pub non_interactive: bool,
+    pub max_age: Option<TimeDelta>,
+    pub offline: bool,
+}
+static OPTS: OnceLock<Opts> = OnceLock::new();
+/// set once in `main`, defaults otherwise
+pub fn opts() -> &'static Opts {
+    OPTS.get_or_init(Opts::default)
+}
+impl Args {
+    /// make [`opts`] available
+    pub fn set_opts(&self) {
+        let opts = Opts {
+            password_stdin: self.password_stdin,
+            non_interactive: self.non_interactive,
+            max_age: self.max_age,
+            offline: self.offline,
+        };
+        if OPTS.set(opts).is_err() {
+            warn!("options were already set");
+        }
+    }
 }
 
 #[derive(Subcommand, Debug, Clone)]
@@ -79,19 +134,51 @@ pub enum Command {
         /// show additional notes/system messages
         #[arg(long, default_value_t = false)]
         notes: bool,
+        /// show messages and notes together, in one listing
+        #[arg(short, long, default_value_t = false, conflicts_with = "notes")]
+        all: bool,
         /// id of the message to render
         id: Option<isize>,
+        /// open the attachments of the message with the default application
+        #[rustfmt::skip]
+        #[arg(short, long, default_value_t = false, requires = "id", conflicts_with = "notes")]
+        open: bool,
+        /// print the original `html` of the message instead of rendering it
+        #[arg(long, default_value_t = false, requires = "id")]
+        raw_html: bool,
+        /// export all messages to the given directory
+        #[arg(long, value_name = "DIR", conflicts_with_all = ["id", "notes", "all"])]
+        export: Option<std::path::PathBuf>,
+        /// format of the export
+        #[arg(long, value_enum, default_value_t = ExportFormat::Md, requires = "export")]
+        format: ExportFormat,
     },
 
     /// information about lessons the user missed
     #[clap(visible_alias = "a")]
+    #[command(group = clap::ArgGroup::new("view").multiple(false))]
     Absences {
         /// filter the subject to show
         #[arg(short, long)]
         subject: Option<String>,
         /// count the number of absences
-        #[arg(short, long, default_value_t = false)]
+        #[arg(short, long, default_value_t = false, group = "view")]
         count: bool,
+        /// missed lessons per subject compared to the ones held, warn near the limits
+        #[arg(long, default_value_t = false, group = "view")]
+        summary: bool,
+        /// late arrivals summed up per subject and month, in lessons worth too
+        #[arg(long, default_value_t = false, group = "view")]
+        lateness: bool,
+        /// missed lessons collapsed into one entry per day, as they're justified
+        #[arg(long, default_value_t = false, group = "view")]
+        by_day: bool,
+        /// what was missed: topics of the lessons, tests written and homework given on them
+        #[arg(long, default_value_t = false, group = "view")]
+        catch_up: bool,
+        /// month by month calendar of the school year, coloured by the absences
+        #[arg(long, default_value_t = false, group = "view")]
+        calendar: bool,
     },
 
     /// information about forecoming exams/tests
@@ -105,6 +192,26 @@ pub enum Command {
         past: bool,
     },
 
+    /// homework to do, by deadline, overdue ones in red
+    #[clap(visible_alias = "hw")]
+    Homework {
+        /// show the homework with this id in full, the start of the id is enough if it's unique
+        #[arg(conflicts_with_all = ["done", "undone"])]
+        id: Option<String>,
+        /// filter the subject to show
+        #[arg(short, long)]
+        subject: Option<String>,
+        /// show homework marked done as well
+        #[arg(short, long, default_value_t = false)]
+        all: bool,
+        /// mark the homework with this id done, the start of the id is enough if it's unique
+        #[arg(long, value_name = "ID", conflicts_with = "undone")]
+        done: Option<String>,
+        /// mark the homework with this id not done, the start of the id is enough if it's unique
+        #[arg(long, value_name = "ID")]
+        undone: Option<String>,
+    },
+
     /// managing users of this program, listing if nothing specified
     #[clap(visible_alias = "u")]
     User {
@@ -116,6 +223,9 @@ pub enum Command {
         /// log in to an existing E-Kréta account
         #[arg(long, default_value_t = false, requires = "userid")]
         login: bool,
+        /// the `azonosító` of the school to log in to, see `rsfilc schools`, asked for if not specified
+        #[arg(long, requires = "login")]
+        school: Option<String>,
         /// switch between existing accounts
         #[rustfmt::skip]
         #[arg(short, long, default_value_t = false, conflicts_with = "login", requires = "userid")]
@@ -123,6 +233,44 @@ pub enum Command {
         /// print the cache directory for a user
         #[arg(long, default_value_t = false, conflicts_with_all = ["logout", "login", "switch"])]
         cache_dir: bool,
+        /// show whether there's a token, when it expires and when each kind of data was last synced, for every user if not specified, without reaching out to the server
+        #[rustfmt::skip]
+        #[arg(long, default_value_t = false, conflicts_with_all = ["logout", "login", "switch", "cache_dir", "revoke"])]
+        status: bool,
+        /// refresh the token now, with `--status` show whether it worked for each user
+        #[rustfmt::skip]
+        #[arg(long, default_value_t = false, conflicts_with_all = ["logout", "login", "switch", "cache_dir", "revoke"])]
+        refresh: bool,
+        /// discard the tokens, keeping the rest of the cache
+        #[rustfmt::skip]
+        #[arg(long, default_value_t = false, conflicts_with_all = ["logout", "login", "switch", "cache_dir"])]
+        revoke: bool,
+    },
+
+    /// inspect and manage the cache, showing its status if nothing specified
+    Cache {
+        #[command(subcommand)]
+        action: Option<CacheAction>,
+        /// act on the cache of every user, not just the selected one
+        #[arg(short, long, default_value_t = false)]
+        all_users: bool,
+    },
+
+    /// bundle the config and every user's cache and downloads to a `.tar.zst`
+    Backup {
+        /// eg. rsfilc.tar.zst
+        file: std::path::PathBuf,
+        /// leave the tokens out, you'll need to log in again after restoring
+        #[arg(long, default_value_t = false)]
+        no_tokens: bool,
+    },
+    /// put everything from a backup back in place
+    Restore {
+        /// made with `rsfilc backup`
+        file: std::path::PathBuf,
+        /// overwrite the config, even if there are users logged in already
+        #[arg(long, default_value_t = false)]
+        force: bool,
     },
 
     /// information about all schools in the `Kréta` database
@@ -136,6 +284,25 @@ pub enum Command {
     /// guided renaming
     Rename,
 }
+#[derive(Subcommand, Debug, Clone)]
+pub enum CacheAction {
+    /// age, size and item count of each kind of cache
+    Status,
+    /// delete the cache of a kind, everything that can be refetched if not specified
+    Clear {
+        /// eg. absence, lesson, msgitem
+        kind: Option<String>,
+    },
+    /// drop cached items from before a date
+    Prune {
+        /// in the format: 2025-09-01
+        #[arg(long)]
+        before: NaiveDate,
+    },
+    /// check every cache file can be loaded
+    Verify,
+}
+
 impl Command {
     pub fn user_needed(&self) -> bool {
         info!("checking whether user is needed for task");
@@ -145,15 +312,24 @@ impl Command {
             switch,
             cache_dir,
             userid: _,
+            school: _,
+            status,
+            refresh,
+            revoke,
         } = &self
         {
+            let session = *status || *refresh || *revoke;
             // we do need one on: nothing, switching, listing
-            let nothing_specified = !logout && !login && !switch && !cache_dir;
+            let nothing_specified = !logout && !login && !switch && !cache_dir && !session;
             return nothing_specified || *switch;
         }
         !matches!(
             self,
-            Command::Schools { search: _ } | Command::Completions { shell: _ }
+            Command::Schools { search: _ }
+                | Command::Completions { shell: _ }
+                | Command::Cache { .. }
+                | Command::Backup { .. }
+                | Command::Restore { .. }
         )
     }
 }
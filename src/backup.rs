@@ -0,0 +1,183 @@
+//! bundling all user data: config, caches and downloads, to move to another device or to archive
+
+use crate::{Res, config::Config, paths, secret};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use tar::EntryType;
+
+/// name of the config in the archive
+const CONFIG: &str = "config.toml";
+/// dir of the caches in the archive
+const CACHE: &str = "cache";
+/// dir of the downloads in the archive
+const DOWNLOADS: &str = "downloads";
+
+/// write everything to `file` as a `.tar.zst`, `tokens` too if asked to
+///
+/// tokens kept in the OS keyring are encrypted with the passphrase, left out if there's none
+pub fn backup(file: &Path, tokens: bool) -> Res<()> {
+    log::info!("backing up to {file:?}, tokens: {tokens}");
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    // it may hold the tokens
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let encoder = zstd::Encoder::new(options.open(file)?, 0)?;
+    let mut tar = tar::Builder::new(encoder);
+
+    let conf_path = Config::path()?;
+    if conf_path.exists() {
+        tar.append_path_with_name(conf_path, CONFIG)?;
+    }
+    let cache_dir = paths::cache_dir("").ok_or("no cache dir found")?;
+    let log = paths::log_path();
+    let skip = |path: &Path| {
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+        // locks, half-written ones, and the log, that's being written while archiving it
+        let temporary = name.ends_with(".lock") || name.ends_with(".tmp");
+        let temporary = temporary || log.as_deref() == Some(path);
+        // added separately, backups of them are left out, as they may be in plaintext
+        temporary || name.starts_with("token_cache.jsonc")
+    };
+    append_dir(&mut tar, &cache_dir, Path::new(CACHE), &skip)?;
+    if tokens {
+        append_tokens(&mut tar, &cache_dir)?;
+    }
+    append_dir(
+        &mut tar,
+        &paths::download_dir(),
+        Path::new(DOWNLOADS),
+        &|_| false,
+    )?;
+
+    tar.into_inner()?.finish()?.flush()?;
+    println!("backed up to {}", file.display());
+    Ok(())
+}
+
+/// add everything in `dir` under `name`, except for what's to `skip`
+fn append_dir<W: Write>(
+    tar: &mut tar::Builder<W>,
+    dir: &Path,
+    name: &Path,
+    skip: &dyn Fn(&Path) -> bool,
+) -> Res<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = name.join(path.file_name().ok_or("invalid file name")?);
+        if path.is_dir() {
+            append_dir(tar, &path, &name, skip)?;
+        } else if !skip(&path) {
+            tar.append_path_with_name(&path, name)?;
+        }
+    }
+    Ok(())
+}
+
+/// add the token of every user, the ones in the keyring taken out of it, encrypted
+fn append_tokens<W: Write>(tar: &mut tar::Builder<W>, cache_dir: &Path) -> Res<()> {
+    for entry in fs::read_dir(cache_dir)? {
+        let dir = entry?.path();
+        let userid = dir.file_name().and_then(|n| n.to_str());
+        let Some(userid) = userid.filter(|_| dir.is_dir()) else {
+            continue;
+        };
+        let Some(path) = paths::cache_path(userid, "token").filter(|p| p.exists()) else {
+            continue;
+        };
+        let name = Path::new(CACHE).join(userid);
+        let name = name.join(path.file_name().ok_or("invalid file name")?);
+        match secret::export(userid) {
+            Ok(Some(content)) => {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(content.len().try_into()?);
+                header.set_mode(0o600);
+                tar.append_data(&mut header, name, content.as_bytes())?;
+            }
+            Ok(None) => tar.append_path_with_name(&path, name)?,
+            Err(e) => {
+                eprintln!("left out the token of {userid}, log in again after restoring: {e}")
+            }
+        }
+    }
+    Ok(())
+}
+
+/// put everything from `file` back in place, won't overwrite a config with users unless `force`
+pub fn restore(file: &Path, force: bool) -> Res<()> {
+    log::info!("restoring from {file:?}");
+    let conf_path = Config::path()?;
+    if !force && Config::load().is_ok_and(|conf| !conf.users.is_empty()) {
+        return Err("there are users logged in already, use `--force` to overwrite them".into());
+    }
+    let open = || -> Res<_> { Ok(tar::Archive::new(zstd::Decoder::new(File::open(file)?)?)) };
+
+    // the config first, as it may tell where the downloads go
+    for entry in open()?.entries()? {
+        let mut entry = entry?;
+        if entry.path()? == Path::new(CONFIG) {
+            unpack(&mut entry, &conf_path)?;
+            break;
+        }
+    }
+    let cache_dir = paths::cache_dir("").ok_or("no cache dir found")?;
+    let download_dir = paths::download_dir_of(&Config::load()?);
+    let log = paths::log_path();
+
+    for entry in open()?.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        if path == Path::new(CONFIG) {
+            continue;
+        }
+        let Some(dest) = destination(&path, &conf_path, &cache_dir, &download_dir) else {
+            log::warn!("skipping unknown entry: {path:?}");
+            continue;
+        };
+        // from older backups, it'd overwrite the one being written
+        if log.as_ref() == Some(&dest) {
+            log::info!("not restoring the log");
+            continue;
+        }
+        unpack(&mut entry, &dest)?;
+    }
+    println!("restored from {}", file.display());
+    Ok(())
+}
+
+/// unpack `entry` to `dest`, if it's a plain file or a dir: links could point anywhere
+fn unpack(entry: &mut tar::Entry<impl Read>, dest: &Path) -> Res<()> {
+    match entry.header().entry_type() {
+        EntryType::Directory => fs::create_dir_all(dest)?,
+        EntryType::Regular => {
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            entry.unpack(dest)?;
+        }
+        kind => {
+            log::warn!("skipping {kind:?} entry for {dest:?}");
+            eprintln!("warning: skipped {dest:?}, as it's not a plain file");
+        }
+    }
+    Ok(())
+}
+
+/// where `path` in the archive belongs, `None` if unknown or trying to escape its place
+fn destination(path: &Path, conf: &Path, cache: &Path, downloads: &Path) -> Option<PathBuf> {
+    if !path.components().all(|c| matches!(c, Component::Normal(_))) {
+        return None;
+    }
+    if path == Path::new(CONFIG) {
+        return Some(conf.to_path_buf());
+    }
+    if let Ok(rest) = path.strip_prefix(CACHE) {
+        return Some(cache.join(rest));
+    }
+    let rest = path.strip_prefix(DOWNLOADS).ok()?;
+    Some(downloads.join(rest))
+}
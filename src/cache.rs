@@ -1,21 +1,343 @@
-use crate::{Res, paths::cache_path};
-use chrono::{DateTime, Local};
-use std::fs::{self, File};
+use crate::{Res, args::CacheAction, homework::HomeworkDone, paths::cache_path, time, utils};
+use chrono::{DateTime, Local, NaiveDate, TimeDelta};
+use ekreta::{
+    Absence, AnnouncedTest, Evaluation, HomeWork, LDateTime, Lesson, MsgItem, MsgOview, NoteMsg,
+    OptIrval, UserInfo,
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
 use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+use time::MyDate;
+use yansi::Paint;
 
-/// save to disk
+/// kinds of cache that can't be refetched, so they're only deleted if explicitly asked for
+pub const IRREPLACEABLE: [&str; 2] = ["token", "homeworkdone"];
+
+pub fn handle(userids: &[String], action: CacheAction, args: &crate::Args) -> Res<()> {
+    match action {
+        CacheAction::Status => status(userids, args),
+        CacheAction::Clear { kind } => {
+            for userid in userids {
+                clear(userid, kind.as_deref())?;
+            }
+            Ok(())
+        }
+        CacheAction::Prune { before } => {
+            for userid in userids {
+                for kind in kinds(userid)? {
+                    prune(userid, &kind, before)?;
+                }
+            }
+            Ok(())
+        }
+        CacheAction::Verify => verify(userids, args),
+    }
+}
+
+/// how old the cache of `kind` may be to be used without reaching out to the server,
+/// `--max-age` overrides what's configured
+pub fn max_age(kind: &str) -> Option<TimeDelta> {
+    if let Some(max_age) = crate::args::opts().max_age {
+        return Some(max_age);
+    }
+    let max_age = crate::config::CONFIG.cache.max_age.get(kind)?;
+    time::parse_age(max_age)
+        .inspect_err(|e| log::warn!("invalid max age for {kind}: {e}"))
+        .ok()
+}
+/// whether the cache of `kind` from `cached_at` can be used without reaching out to the server
+pub fn is_fresh(kind: &str, cached_at: LDateTime) -> bool {
+    max_age(kind).is_some_and(|max_age| Local::now() - cached_at < max_age)
+}
+
+/// when the data shown in this run was cached, the oldest one, and when fetched, the first one
+static SOURCES: Mutex<(Option<LDateTime>, Option<LDateTime>)> = Mutex::new((None, None));
+/// data cached at `cached_at` is shown
+pub fn note_cached(cached_at: LDateTime) {
+    if let Ok(mut sources) = SOURCES.lock() {
+        sources.0 = Some(sources.0.map_or(cached_at, |oldest| oldest.min(cached_at)));
+    }
+}
+/// freshly fetched data is shown
+pub fn note_fetched() {
+    if let Ok(mut sources) = SOURCES.lock() {
+        sources.1.get_or_insert_with(Local::now);
+    }
+}
+/// when the data shown was fetched, and whether it's from cache, `None` if nothing was shown
+pub fn data_age() -> Option<(LDateTime, bool)> {
+    let (cached, fetched) = *SOURCES.lock().ok()?;
+    cached.map(|t| (t, true)).or(fetched.map(|t| (t, false)))
+}
+
+/// kinds of cache saved for `userid`
+fn kinds(userid: &str) -> Res<Vec<String>> {
+    let cd = crate::paths::cache_dir(userid).ok_or("no cache dir found for user")?;
+    let mut kinds = fs::read_dir(cd)?
+        .filter_map(|entry| {
+            let name = entry.ok()?.file_name();
+            let kind = name.to_str()?.strip_suffix("_cache.jsonc")?;
+            Some(kind.to_string())
+        })
+        .collect::<Vec<_>>();
+    kinds.sort_unstable();
+    Ok(kinds)
+}
+
+/// when each kind of data was last synced for `userid`
+pub fn synced(userid: &str) -> Res<BTreeMap<String, LDateTime>> {
+    let mut synced = BTreeMap::new();
+    for kind in kinds(userid)? {
+        if IRREPLACEABLE.contains(&kind.as_str()) || kind.ends_with(COVERAGE) {
+            continue;
+        }
+        let cp = cache_path(userid, &kind).ok_or("couldn't get cache path")?;
+        let content = fs::read_to_string(cp)?;
+        if let Some((cached_at, _, _)) = parse(&content) {
+            synced.insert(kind, cached_at);
+        }
+    }
+    Ok(synced)
+}
+
+/// state of a single cache file
+#[derive(Debug, Serialize)]
+struct Status {
+    userid: String,
+    kind: String,
+    cached_at: Option<LDateTime>,
+    /// in bytes
+    size: u64,
+    /// `None` if the content isn't even valid json
+    items: Option<usize>,
+}
+fn status(userids: &[String], args: &crate::Args) -> Res<()> {
+    let mut statuses = Vec::new();
+    for userid in userids {
+        for kind in kinds(userid)? {
+            let cp = cache_path(userid, &kind).ok_or("couldn't get cache path")?;
+            let size = fs::metadata(&cp)?.len();
+            // not `load`, that'd move a corrupt file out of the way
+            let content = fs::read_to_string(cp)?;
+            let parsed = parse(&content).map(|(cached_at, _, content)| (cached_at, content));
+            let (cached_at, content) = parsed.unzip();
+            let items = content
+                .and_then(|c| serde_json::from_str::<Value>(&c).ok())
+                .map(|val| val.as_array().map_or(1, Vec::len));
+            let userid = userid.clone();
+            statuses.push(Status {
+                userid,
+                kind,
+                cached_at,
+                size,
+                items,
+            });
+        }
+    }
+    let headers = ["FELHASZNÁLÓ", "FAJTA", "MENTVE", "MÉRET", "ELEMEK"].into_iter();
+    let disp = if args.machine {
+        None
+    } else {
+        Some(display_status)
+    };
+    utils::print_table(&statuses, headers, false, usize::MAX, disp)
+}
+fn display_status(status: &Status) -> Vec<String> {
+    let cached_at = status.cached_at.map_or("sérült".into(), |t| {
+        format!("{} ({})", t.pretty(), time::ago(&t))
+    });
+    let items = status.items.map_or("?".into(), |n| n.to_string());
+    let size = human_size(status.size);
+    vec![
+        status.userid.clone(),
+        status.kind.clone(),
+        cached_at,
+        size,
+        items,
+    ]
+}
+/// eg. 2.4 KiB
+fn human_size(bytes: u64) -> String {
+    let mut size = bytes as f64;
+    for unit in ["B", "KiB", "MiB"] {
+        if size < 1024. {
+            return format!("{size:.1} {unit}");
+        }
+        size /= 1024.;
+    }
+    format!("{size:.1} GiB")
+}
+
+/// delete the cache of `kind` or everything that can be refetched if `None`
+pub fn clear(userid: &str, kind: Option<&str>) -> Res<()> {
+    let kinds = if let Some(kind) = kind {
+        vec![kind.to_string(), format!("{kind}{COVERAGE}")]
+    } else {
+        let all = kinds(userid)?.into_iter();
+        all.filter(|k| !IRREPLACEABLE.contains(&k.as_str()))
+            .collect()
+    };
+    for kind in kinds {
+        // the keyring may hold a token even without a cache of it
+        if kind == "token" {
+            crate::secret::forget(userid);
+        }
+        let cp = cache_path(userid, &kind).ok_or("couldn't get cache path")?;
+        if !cp.exists() {
+            log::warn!("no {kind} cache for {userid}");
+            continue;
+        }
+        let _lock = lock(&cp, true)?;
+        log::info!("deleting {cp:?}");
+        fs::remove_file(&cp)?;
+        for leftover in ["jsonc.corrupt", "jsonc.bak"].map(|ext| cp.with_extension(ext)) {
+            if leftover.exists() {
+                fs::remove_file(leftover)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// drop cached items of `kind` from before `before`, keeping the time of caching as-is
+fn prune(userid: &str, kind: &str, before: NaiveDate) -> Res<()> {
+    let Some((cached_at, content)) = load(userid, kind) else {
+        return Ok(());
+    };
+    let pruned = rework(kind, &content, Some(before))?;
+    if pruned != content {
+        log::info!("pruning {kind} cache of {userid} before {before}");
+        store_as_of(userid, kind, &pruned, cached_at)?;
+    }
+    Ok(())
+}
+
+/// result of checking a cache file
+#[derive(Debug, Serialize)]
+struct Verdict {
+    userid: String,
+    kind: String,
+    error: Option<String>,
+}
+fn verify(userids: &[String], args: &crate::Args) -> Res<()> {
+    let mut verdicts = Vec::new();
+    for userid in userids {
+        for kind in kinds(userid)? {
+            let cp = cache_path(userid, &kind).ok_or("couldn't get cache path")?;
+            // not `load`, that'd move a corrupt file out of the way
+            let content = fs::read_to_string(cp)?;
+            let error = parse(&content)
+                .ok_or("invalid header".into())
+                .and_then(|(_, version, content)| migrate(&kind, version, content))
+                .and_then(|content| rework(&kind, &content, None))
+                .err()
+                .map(|e| e.to_string());
+            let userid = userid.clone();
+            verdicts.push(Verdict {
+                userid,
+                kind,
+                error,
+            });
+        }
+    }
+    let broken = verdicts.iter().filter(|v| v.error.is_some()).count();
+    let headers = ["FELHASZNÁLÓ", "FAJTA", "ÁLLAPOT"].into_iter();
+    let disp = if args.machine {
+        None
+    } else {
+        Some(display_verdict)
+    };
+    utils::print_table(&verdicts, headers, false, usize::MAX, disp)?;
+    if broken > 0 {
+        return Err(format!("{broken} invalid cache file(s)").into());
+    }
+    Ok(())
+}
+fn display_verdict(verdict: &Verdict) -> Vec<String> {
+    let state = verdict
+        .error
+        .as_ref()
+        .map_or("rendben".green().to_string(), |e| e.red().to_string());
+    vec![verdict.userid.clone(), verdict.kind.clone(), state]
+}
+
+/// check `content` is a valid cache of `kind`, and drop the items from before `before`
+///
+/// `kind` as in [`utils::type_to_kind_name`]
+fn rework(kind: &str, content: &str, before: Option<NaiveDate>) -> Res<String> {
+    match kind {
+        "lesson" => retain::<Lesson>(content, before, |l| l.kezdet_idopont),
+        "evaluation" => retain::<Evaluation>(content, before, |e| e.keszites_datuma),
+        "announcedtest" => retain::<AnnouncedTest>(content, before, |t| t.datum),
+        "homework" => retain::<HomeWork>(content, before, |hw| hw.hatarido_datuma),
+        "absence" => retain::<Absence>(content, before, |a| a.ora.kezdo_datum),
+        "notemsg" => retain::<NoteMsg>(content, before, |nm| nm.datum),
+        "msgitem" => retain::<MsgItem>(content, before, |m| m.uzenet.kuldes_datum),
+        "msgoview" => retain::<MsgOview>(content, before, |m| m.uzenet_kuldes_datum),
+        k if k.ends_with(COVERAGE) => {
+            let mut coverage = serde_json::from_str::<Coverage>(content)?;
+            let Some(before) = before else {
+                return Ok(content.to_string());
+            };
+            coverage.forget_before(before);
+            Ok(serde_json::to_string(&coverage)?)
+        }
+        "token" => keep::<crate::secret::Stored>(content),
+        "userinfo" => keep::<UserInfo>(content),
+        "homeworkdone" => keep::<HomeworkDone>(content),
+        _ => Err(format!("unknown kind of cache: {kind}").into()),
+    }
+}
+fn retain<T: Serialize + DeserializeOwned>(
+    content: &str,
+    before: Option<NaiveDate>,
+    when: impl Fn(&T) -> LDateTime,
+) -> Res<String> {
+    let mut items = serde_json::from_str::<Vec<T>>(content)?;
+    let Some(before) = before else {
+        return Ok(content.to_string());
+    };
+    let orig_len = items.len();
+    items.retain(|item| when(item).date_naive() >= before);
+    if items.len() == orig_len {
+        return Ok(content.to_string());
+    }
+    Ok(serde_json::to_string(&items)?)
+}
+fn keep<T: DeserializeOwned>(content: &str) -> Res<String> {
+    serde_json::from_str::<T>(content)?;
+    Ok(content.to_string())
+}
+
+/// save to disk: written to a temporary file first, then renamed in place,
+/// so readers never see a half-written cache, even if we get killed mid-write
 pub fn store(userid: &str, kind: &str, content: &str) -> Res<()> {
+    store_as_of(userid, kind, content, Local::now())
+}
+/// [`store`], but as if cached at `cached_at`
+pub fn store_as_of(userid: &str, kind: &str, content: &str, cached_at: LDateTime) -> Res<()> {
     let cp = cache_path(userid, kind).ok_or("couldn't get cache path")?;
-    let mut f = File::create(&cp)?;
+    let _lock = lock(&cp, true)?;
     log::info!("caching to {cp:?}");
 
-    writeln!(f, "//{}", Local::now().to_rfc3339())?;
-    writeln!(f, "{content}")?;
+    let tmp = cp.with_extension("jsonc.tmp");
+    let mut f = File::create(&tmp)?;
+    f.write_all(file_content(content, cached_at).as_bytes())?;
+    f.sync_all()?;
+    fs::rename(tmp, cp)?;
 
     Ok(())
 }
+/// what a cache file of `content`, cached at `cached_at` looks like
+pub fn file_content(content: &str, cached_at: LDateTime) -> String {
+    format!("//{}\n//v{VERSION}\n{content}\n", cached_at.to_rfc3339())
+}
 
-/// load from disk
+/// load from disk, migrated to [`VERSION`], a corrupt cache is moved out of the way
 pub fn load(userid: &str, kind: &str) -> Option<(DateTime<Local>, String)> {
     let cp = cache_path(userid, kind)?;
     log::info!("loading cache from {cp:?}");
@@ -23,15 +345,128 @@ pub fn load(userid: &str, kind: &str) -> Option<(DateTime<Local>, String)> {
         log::warn!("no saved cache exists");
         return None;
     }
-    let content = fs::read_to_string(cp).ok()?;
+    let guard = lock(&cp, false).ok()?;
+    let content = fs::read_to_string(&cp).ok()?;
+    drop(guard);
+    let Some((t, version, content)) = parse(&content) else {
+        set_aside(userid, kind);
+        return None;
+    };
+    if version == VERSION {
+        return Some((t, content));
+    }
+    match migrate(kind, version, content) {
+        Ok(migrated) => Some((t, migrated)),
+        Err(e) => {
+            log::error!("couldn't migrate {kind} cache: {e}");
+            backup(userid, kind);
+            None
+        }
+    }
+}
+/// split `content` to the time of caching, the version of the format and the cached data
+fn parse(content: &str) -> Option<(DateTime<Local>, u32, String)> {
     let mut cl = content.lines();
-    let t = cl.next()?;
     // removing "//" (comment sequence)
-    let t = DateTime::parse_from_rfc3339(&t[2..]).ok()?;
+    let t = cl.next()?.strip_prefix("//")?;
+    let t = DateTime::parse_from_rfc3339(t).ok()?;
+
+    let mut c = cl.next()?;
+    // no version line before v1
+    let mut version = 0;
+    if let Some(v) = c.strip_prefix("//v") {
+        version = v.parse().ok()?;
+        c = cl.next()?;
+    }
+
+    Some((t.into(), version, c.to_string()))
+}
+
+/// version of the cache format, bump when a cached type changes shape, with a step in [`migrate`]
+pub const VERSION: u32 = 1;
+
+/// bring `content` of `kind` from `version` to [`VERSION`]
+fn migrate(kind: &str, version: u32, content: String) -> Res<String> {
+    if version > VERSION {
+        return Err(format!("{kind} cached by a newer version (v{version})").into());
+    }
+    let mut content = content;
+    for from in version..VERSION {
+        log::info!("migrating {kind} cache from v{from}");
+        content = match from {
+            // only the version line was added
+            0 => content,
+            _ => return Err(format!("no migration for {kind} from v{from}").into()),
+        };
+    }
+    Ok(content)
+}
 
-    let c = cl.next()?.to_string();
+/// best-effort loading of `content`: only the items of the cached array that still deserialize as an item of `D`
+///
+/// `None` if not an array, or nothing could be kept
+pub fn lenient<D: DeserializeOwned>(content: &str) -> Option<D> {
+    let Value::Array(items) = serde_json::from_str(content).ok()? else {
+        return None;
+    };
+    let orig_len = items.len();
+    let fits = |item: &Value| serde_json::from_value::<D>(Value::Array(vec![item.clone()])).is_ok();
+    let kept = items.into_iter().filter(fits).collect::<Vec<_>>();
+    if kept.is_empty() {
+        return None;
+    }
+    log::warn!("leniently loaded {} of {orig_len} cached items", kept.len());
+    serde_json::from_value(Value::Array(kept)).ok()
+}
 
-    Some((t.into(), c))
+/// copy the cache of `kind` to `*.bak`, so it isn't lost when overwritten,
+/// an existing backup is kept, that's the older one
+pub fn backup(userid: &str, kind: &str) {
+    let Some(cp) = cache_path(userid, kind) else {
+        return;
+    };
+    let to = cp.with_extension("jsonc.bak");
+    if to.exists() {
+        log::info!("backup already exists at {to:?}");
+        return;
+    }
+    log::warn!("backing up {kind} cache to {to:?}");
+    eprintln!("warning: couldn't fully load {kind} cache, kept a backup at {to:?}");
+    if let Err(e) = fs::copy(&cp, &to) {
+        log::error!("couldn't back up cache: {e:?}");
+    }
+}
+
+/// move the corrupt cache of `kind` aside as `*.corrupt`, so it's refetched next time, but kept for inspection
+pub fn set_aside(userid: &str, kind: &str) {
+    let Some(cp) = cache_path(userid, kind) else {
+        return;
+    };
+    let Ok(_lock) = lock(&cp, true) else { return };
+    let to = cp.with_extension("jsonc.corrupt");
+    log::error!("corrupt cache, moving it to {to:?}");
+    eprintln!("warning: corrupt {kind} cache, moved to {to:?}");
+    if let Err(e) = fs::rename(&cp, &to) {
+        log::error!("couldn't move corrupt cache: {e:?}");
+    }
+}
+
+/// advisory lock for the cache at `cp`, held until the returned [`File`] is dropped
+///
+/// a separate lock file is used, as the cache itself gets replaced on write
+fn lock(cp: &Path, exclusive: bool) -> Res<File> {
+    let lock_path = cp.with_extension("lock");
+    let f = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(lock_path)?;
+    if exclusive {
+        f.lock()?
+    } else {
+        f.lock_shared()?
+    }
+    Ok(f)
 }
 /// delete all cache and logs as well
 pub fn delete_dir(userid: &str) -> Res<()> {
@@ -44,3 +479,210 @@ pub fn delete_dir(userid: &str) -> Res<()> {
     }
     Ok(())
 }
+
+/// suffix of the kind storing the [`Coverage`] of a kind
+const COVERAGE: &str = "_coverage";
+
+/// an interval of dates fetched `at`, open ends meaning no limit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Fetched {
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    at: LDateTime,
+}
+impl Fetched {
+    fn from(&self) -> NaiveDate {
+        self.from.unwrap_or(NaiveDate::MIN)
+    }
+    fn to(&self) -> NaiveDate {
+        self.to.unwrap_or(NaiveDate::MAX)
+    }
+}
+
+/// which date intervals of a kind were fetched and when,
+/// so empty cache for an interval can mean "no data" instead of "never fetched"
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Coverage(Vec<Fetched>);
+impl Coverage {
+    /// of `kind` for `userid`, empty if nothing's recorded
+    pub fn load(userid: &str, kind: &str) -> Self {
+        let loaded = load(userid, &format!("{kind}{COVERAGE}"));
+        let deserd = loaded.and_then(|(_, content)| serde_json::from_str(&content).ok());
+        deserd.unwrap_or_default()
+    }
+    pub fn store(&self, userid: &str, kind: &str) -> Res<()> {
+        let content = serde_json::to_string(self)?;
+        store(userid, &format!("{kind}{COVERAGE}"), &content)
+    }
+    /// record `irval` as fetched just now
+    pub fn add(&mut self, irval: OptIrval) {
+        let (from, to) = irval;
+        let fetched = Fetched {
+            from,
+            to,
+            at: Local::now(),
+        };
+        // superseded by the new one
+        self.0
+            .retain(|f| f.from() < fetched.from() || f.to() > fetched.to());
+        self.0.push(fetched);
+    }
+    /// whether every day of `irval` was fetched at some point
+    pub fn covers(&self, irval: OptIrval) -> bool {
+        let (from, to) = irval;
+        let covered = self.0.iter().map(|f| (f.from(), f.to()));
+        let reached = reach(from.unwrap_or(NaiveDate::MIN), covered);
+        reached.is_none_or(|reached| reached > to.unwrap_or(NaiveDate::MAX))
+    }
+    /// the first day from `from` (inclusive) that can still change on the server,
+    /// for data that can't change once it's there: days before the day of fetching are settled
+    ///
+    /// `None` if nothing's settled from `from`
+    pub fn settled_till(&self, from: Option<NaiveDate>) -> Option<NaiveDate> {
+        let from = from.unwrap_or(NaiveDate::MIN);
+        let settled = self.0.iter().filter_map(|f| {
+            let last_settled = f.at.date_naive().pred_opt()?;
+            Some((f.from(), f.to().min(last_settled)))
+        });
+        let reached = reach(from, settled).unwrap_or(NaiveDate::MAX);
+        (reached > from).then_some(reached)
+    }
+    /// forget about anything fetched before `before`, as it's been pruned
+    fn forget_before(&mut self, before: NaiveDate) {
+        self.0.retain(|f| f.to() >= before);
+        for fetched in &mut self.0 {
+            fetched.from = Some(fetched.from().max(before));
+        }
+    }
+}
+/// first day from `start` not in any of the (inclusive) `intervals`, `None` if they reach the end of time
+fn reach(
+    start: NaiveDate,
+    intervals: impl Iterator<Item = (NaiveDate, NaiveDate)>,
+) -> Option<NaiveDate> {
+    let mut intervals = intervals
+        .filter(|(from, to)| from <= to)
+        .collect::<Vec<_>>();
+    intervals.sort_unstable();
+    let mut reached = start;
+    for (from, to) in intervals {
+        if from > reached {
+            break;
+        }
+        if to >= reached {
+            reached = to.succ_opt()?;
+        }
+    }
+    Some(reached)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 9, d).unwrap()
+    }
+    /// fetched on day `at`
+    fn fetched(from: Option<NaiveDate>, to: Option<NaiveDate>, at: u32) -> Fetched {
+        let at = day(at).and_hms_opt(12, 0, 0).unwrap();
+        let at = at.and_local_timezone(Local).unwrap();
+        Fetched { from, to, at }
+    }
+
+    #[test]
+    fn reach_follows_overlapping_and_adjacent() {
+        let intervals = [(day(5), day(9)), (day(1), day(3)), (day(4), day(6))];
+        assert_eq!(reach(day(2), intervals.into_iter()), Some(day(10)));
+        assert_eq!(reach(day(1), [(day(3), day(9))].into_iter()), Some(day(1)));
+        assert_eq!(reach(day(1), std::iter::empty()), Some(day(1)));
+        // reversed intervals are empty
+        assert_eq!(reach(day(1), [(day(9), day(1))].into_iter()), Some(day(1)));
+    }
+    #[test]
+    fn reach_end_of_time() {
+        let open = [(NaiveDate::MIN, NaiveDate::MAX)];
+        assert_eq!(reach(NaiveDate::MIN, open.into_iter()), None);
+        assert_eq!(reach(day(1), [(day(1), NaiveDate::MAX)].into_iter()), None);
+        assert_eq!(
+            reach(NaiveDate::MAX, std::iter::empty()),
+            Some(NaiveDate::MAX)
+        );
+    }
+
+    #[test]
+    fn add_drops_superseded() {
+        let mut coverage = Coverage::default();
+        coverage.add((Some(day(1)), Some(day(10))));
+        coverage.add((Some(day(5)), Some(day(6))));
+        assert_eq!(coverage.0.len(), 2);
+        coverage.add((None, Some(day(10))));
+        assert_eq!(coverage.0.len(), 1);
+        coverage.add((None, None));
+        assert_eq!(coverage.0.len(), 1);
+        assert_eq!((coverage.0[0].from, coverage.0[0].to), (None, None));
+    }
+
+    #[test]
+    fn covers_bounded() {
+        let mut coverage = Coverage::default();
+        assert!(!coverage.covers((Some(day(1)), Some(day(1)))));
+        coverage.add((Some(day(1)), Some(day(3))));
+        coverage.add((Some(day(4)), Some(day(7))));
+        assert!(coverage.covers((Some(day(2)), Some(day(7)))));
+        assert!(coverage.covers((Some(day(5)), Some(day(5)))));
+        assert!(!coverage.covers((Some(day(2)), Some(day(8)))));
+        assert!(!coverage.covers((None, Some(day(3)))));
+        assert!(!coverage.covers((Some(day(2)), None)));
+    }
+    #[test]
+    fn covers_open_ends() {
+        let mut coverage = Coverage::default();
+        coverage.add((Some(day(10)), None));
+        assert!(coverage.covers((Some(day(10)), None)));
+        assert!(coverage.covers((Some(day(20)), Some(NaiveDate::MAX))));
+        assert!(!coverage.covers((Some(day(9)), None)));
+        assert!(!coverage.covers((None, None)));
+        coverage.add((None, Some(day(9))));
+        assert!(coverage.covers((None, None)));
+        assert!(coverage.covers((Some(NaiveDate::MIN), Some(NaiveDate::MAX))));
+    }
+
+    #[test]
+    fn settled_till_day_of_fetching() {
+        let coverage = Coverage(vec![fetched(None, None, 10)]);
+        assert_eq!(coverage.settled_till(None), Some(day(10)));
+        assert_eq!(coverage.settled_till(Some(day(3))), Some(day(10)));
+        assert_eq!(coverage.settled_till(Some(day(10))), None);
+        assert_eq!(coverage.settled_till(Some(day(20))), None);
+    }
+    #[test]
+    fn settled_till_bounded() {
+        let coverage = Coverage(vec![
+            fetched(Some(day(1)), Some(day(5)), 20),
+            fetched(Some(day(6)), None, 8),
+        ]);
+        assert_eq!(coverage.settled_till(Some(day(1))), Some(day(8)));
+        assert_eq!(coverage.settled_till(Some(day(6))), Some(day(8)));
+        // nothing fetched before the 1st
+        assert_eq!(coverage.settled_till(None), None);
+        // fetched on the day: nothing's settled
+        let coverage = Coverage(vec![fetched(Some(day(1)), None, 1)]);
+        assert_eq!(coverage.settled_till(Some(day(1))), None);
+    }
+
+    #[test]
+    fn forget_before_clamps() {
+        let mut coverage = Coverage(vec![
+            fetched(Some(day(1)), Some(day(4)), 10),
+            fetched(Some(day(3)), Some(day(8)), 10),
+            fetched(None, None, 10),
+        ]);
+        coverage.forget_before(day(5));
+        let left = coverage.0.iter().map(|f| (f.from, f.to));
+        let left = left.collect::<Vec<_>>();
+        assert_eq!(left, [(Some(day(5)), Some(day(8))), (Some(day(5)), None)]);
+        assert!(coverage.covers((Some(day(5)), None)));
+        assert!(!coverage.covers((Some(day(4)), None)));
+    }
+}
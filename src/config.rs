@@ -1,19 +1,101 @@
 use crate::{Res, User};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::{BTreeMap, BTreeSet};
 use std::{path::PathBuf, sync::LazyLock};
 
 pub const APP_NAME: &str = "rsfilc";
 const CONFIG_NAME: &str = "config";
-/// configurations: users, default user, renames
+/// name of the internal `html` renderer in [`Config::renderers`]
+pub const BUILTIN_RENDERER: &str = "builtin";
+/// configurations: users, default user, renames, download dir, renderers, absence limits, cache freshness
 /// loaded on first use, clone and mutate if needed, careful with use afterwards
 pub static CONFIG: LazyLock<Config> = LazyLock::new(|| Config::load().unwrap());
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Config {
     pub default_userid: String,
+    // NOTE: plain values first, toml can't have them after tables
+    /// where to save attachments, `Downloads/rsfilc` if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub download_dir: Option<PathBuf>,
+    /// commands that read `html` from stdin and print text, tried in order until one succeeds
+    #[serde(default = "default_renderers")]
+    pub renderers: Vec<String>,
     pub users: BTreeSet<User>,
     pub rename: BTreeMap<String, String>,
+    #[serde(default)]
+    pub absence_limits: AbsenceLimits,
+    #[serde(default)]
+    pub cache: CacheConfig,
+}
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            default_userid: String::new(),
+            download_dir: None,
+            renderers: default_renderers(),
+            users: BTreeSet::new(),
+            rename: BTreeMap::new(),
+            absence_limits: AbsenceLimits::default(),
+            cache: CacheConfig::default(),
+        }
+    }
+}
+/// how many lessons one may miss, defaults are the ones Hungarian law sets
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AbsenceLimits {
+    /// percentage of the lessons of a subject
+    pub subject_percent: f32,
+    /// number of lessons in a school year
+    pub total: usize,
+    /// warn if this fraction of a limit is reached
+    pub warn_at: f32,
+}
+impl Default for AbsenceLimits {
+    fn default() -> Self {
+        Self {
+            subject_percent: 30.,
+            total: 250,
+            warn_at: 0.8,
+        }
+    }
+}
+
+/// how long cached data may be used without reaching out to the server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    /// kind (eg. lesson, evaluation, absence): max age, like "30s", "10m", "2h" or "1d"
+    #[serde(deserialize_with = "over_default_ages")]
+    pub max_age: BTreeMap<String, String>,
+}
+impl Default for CacheConfig {
+    fn default() -> Self {
+        // a week, then a day of it is often shown right after each other
+        let max_age = [("lesson", "8s")];
+        let max_age = max_age.map(|(kind, age)| (kind.to_string(), age.to_string()));
+        Self {
+            max_age: max_age.into(),
+        }
+    }
+}
+/// configured ages on top of the default ones, so setting one kind doesn't drop the rest
+fn over_default_ages<'de, D: Deserializer<'de>>(
+    de: D,
+) -> Result<BTreeMap<String, String>, D::Error> {
+    let mut max_age = CacheConfig::default().max_age;
+    max_age.extend(BTreeMap::deserialize(de)?);
+    Ok(max_age)
+}
+
+fn default_renderers() -> Vec<String> {
+    let renderers = [
+        "w3m -dump -T text/html",
+        "lynx -stdin -dump",
+        BUILTIN_RENDERER,
+    ];
+    renderers.map(String::from).into()
 }
 impl Config {
     pub fn load() -> Res<Config> {
@@ -27,6 +109,7 @@ impl Config {
     }
     pub fn logout(&mut self, name: impl AsRef<str>) {
         self.users.retain(|usr| usr.userid != name.as_ref());
+        crate::secret::forget(name.as_ref());
         if self.default_userid == name.as_ref() {
             let _ = crate::cache::delete_dir(name.as_ref());
             // set default to the first element, not to die
@@ -0,0 +1,122 @@
+//! homework the student got
+
+use crate::{messages::render_html, time::MyDate, user::User, utils};
+use chrono::Local;
+use ekreta::{HomeWork, Res};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use yansi::Paint;
+
+/// uids of [`HomeWork`] marked done, only stored locally, as Kréta doesn't track it for students
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct HomeworkDone(pub BTreeSet<String>);
+
+pub fn handle(
+    user: &User,
+    id: Option<String>,
+    subj: Option<String>,
+    all: bool,
+    mark: Option<(String, bool)>,
+    args: &crate::Args,
+) -> Res<()> {
+    let homework = user.get_homework((None, None))?;
+    if let Some(id) = id {
+        let hw = find(&homework, &id)?;
+        return if args.machine {
+            utils::print_json(hw)
+        } else {
+            utils::print_paged(&disp_full(hw))
+        };
+    }
+    let mut done = user.get_homework_done();
+    if let Some((id, is_done)) = mark {
+        let hw = find(&homework, &id)?;
+        if is_done {
+            done.0.insert(hw.uid.clone());
+        } else {
+            done.0.remove(&hw.uid);
+        }
+        return user.store_homework_done(&done);
+    }
+    let mut entries = homework
+        .iter()
+        .map(|homework| Entry {
+            done: done.0.contains(&homework.uid),
+            homework,
+        })
+        .collect::<Vec<_>>();
+    if !all {
+        entries.retain(|entry| !entry.done);
+    }
+    if let Some(subject) = subj {
+        log::info!("filtering homework by subject: {subject}");
+        entries.retain(|entry| subj_matches(entry.homework, &subject));
+    }
+    let headers = ["ID", "TANTÁRGY", "FELADAT", "HATÁRIDŐ", "FELADVA", "TANÁR"].into_iter();
+    let disp = if args.machine { None } else { Some(display) };
+    utils::print_table(&entries, headers, args.reverse, args.number, disp)
+}
+
+/// the [`HomeWork`] with `id` as its uid, or as the start of only its uid
+fn find<'a>(homework: &'a [HomeWork], id: &str) -> Res<&'a HomeWork> {
+    if let Some(hw) = homework.iter().find(|hw| hw.uid == id) {
+        return Ok(hw);
+    }
+    let mut matching = homework.iter().filter(|hw| hw.uid.starts_with(id));
+    match (matching.next(), matching.next()) {
+        (Some(hw), None) => Ok(hw),
+        (Some(_), Some(_)) => Err(format!("more homework with an id starting with: {id}").into()),
+        (None, _) => Err(format!("can't find homework with id: {id}").into()),
+    }
+}
+
+/// a [`HomeWork`] in the listing
+#[derive(Debug, Serialize)]
+struct Entry<'a> {
+    done: bool,
+    #[serde(flatten)]
+    homework: &'a HomeWork,
+}
+
+/// whether the subject of `hw` matches `subj`
+pub fn subj_matches(hw: &HomeWork, subj: &str) -> bool {
+    hw.tantargy_neve
+        .to_lowercase()
+        .contains(&subj.to_lowercase())
+}
+
+/// the task of `hw` as plain text, by the builtin renderer, as it's done for every row of a listing
+pub fn task(hw: &HomeWork) -> String {
+    nanohtml2text::html2text(&hw.szoveg).trim().to_string()
+}
+
+/// everything about `hw`, the task rendered like messages
+fn disp_full(hw: &HomeWork) -> String {
+    let mut f = String::new();
+    _ = writeln!(&mut f, "| {}", hw.tantargy_neve);
+    _ = writeln!(&mut f, "| Határidő: {}", hw.hatarido_datuma.pretty());
+    _ = writeln!(&mut f, "| Feladva: {}", hw.feladas_datuma.pretty());
+    _ = writeln!(&mut f, "| {}", hw.rogzito_tanar_neve);
+    let rendered = render_html(&hw.szoveg);
+    _ = write!(&mut f, "\n{rendered}");
+    f
+}
+
+fn display(entry: &Entry) -> Vec<String> {
+    let hw = entry.homework;
+    let id = hw.uid.clone();
+    let subj = hw.tantargy_neve.clone();
+    let task = if entry.done {
+        format!("{} {}", "kész:".green(), task(hw).dim())
+    } else if hw.hatarido_datuma < Local::now() {
+        task(hw).red().to_string()
+    } else {
+        task(hw)
+    };
+    let due = hw.hatarido_datuma.pretty();
+    let given = hw.feladas_datuma.pretty();
+    let teacher = hw.rogzito_tanar_neve.clone();
+
+    vec![id, subj, task, due, given, teacher]
+}
@@ -11,13 +11,16 @@ use user::User;
 mod absences;
 mod announced;
 mod args;
+mod backup;
 mod cache;
 mod config;
 mod evals;
+mod homework;
 mod information;
 mod messages;
 mod paths;
 mod schools;
+mod secret;
 mod time;
 mod timetable;
 mod user;
@@ -30,8 +33,22 @@ fn main() -> Res<()> {
     set_up_logger(cli_args.verbosity)?;
     // respect `NO_COLOR`
     yansi::whenever(yansi::Condition::YES_COLOR);
+    cli_args.set_opts();
+    let machine = cli_args.machine;
     // handle cli args and execute program
-    run(cli_args)?;
+    if let Err(e) = run(cli_args) {
+        if e.is::<args::NeedsInteraction>() {
+            error!("{e}");
+            eprintln!("error: {e}");
+            std::process::exit(args::NEEDS_INTERACTION_EXIT);
+        }
+        return Err(e);
+    }
+    // let them know they're looking at older data
+    if !machine && let Some((cached_at, true)) = cache::data_age() {
+        let footer = format!("mentett adat, {}", time::ago(&cached_at));
+        eprintln!("{}", yansi::Paint::dim(&footer));
+    }
 
     Ok(())
 }
@@ -89,25 +106,107 @@ fn run(args: Args) -> Res<()> {
             ghost,
         } => evals::handle(&user, filter, subj, &ghost, average, &args),
 
-        Command::Messages { notes, id } => {
-            if notes {
-                messages::handle_note_msgs(&user, id, &args)
+        Command::Messages {
+            notes,
+            all,
+            id,
+            open,
+            raw_html,
+            export,
+            format,
+        } => {
+            if let Some(dir) = export {
+                messages::export(&user, &dir, format)
+            } else if all {
+                messages::handle_all(&user, id, open, raw_html, &args)
+            } else if notes {
+                messages::handle_note_msgs(&user, id, raw_html, &args)
             } else {
-                messages::handle(&user, id, &args)
+                messages::handle(&user, id, open, raw_html, &args)
             }
         }
 
-        Command::Absences { count, subject } => absences::handle(&user, subject, count, &args),
+        Command::Absences {
+            subject,
+            count,
+            summary,
+            lateness,
+            by_day,
+            catch_up,
+            calendar,
+        } => {
+            let view = if count {
+                absences::View::Count
+            } else if summary {
+                absences::View::Summary
+            } else if lateness {
+                absences::View::Lateness
+            } else if by_day {
+                absences::View::ByDay
+            } else if catch_up {
+                absences::View::CatchUp
+            } else if calendar {
+                absences::View::Calendar
+            } else {
+                absences::View::List
+            };
+            absences::handle(&user, subject, view, &args)
+        }
 
         Command::Tests { subject, past } => announced::handle(past, &user, subject, &args),
 
+        Command::Homework {
+            id,
+            subject,
+            all,
+            done,
+            undone,
+        } => {
+            let mark = done.map(|id| (id, true)).or(undone.map(|id| (id, false)));
+            homework::handle(&user, id, subject, all, mark, &args)
+        }
+
         Command::User {
             logout,
             login,
             switch,
             cache_dir,
             userid,
-        } => user::handle(userid, login, logout, switch, cache_dir, &args),
+            school,
+            status,
+            refresh,
+            revoke,
+        } => {
+            if status || refresh || revoke {
+                user::handle_session(userid, status, refresh, revoke, &args)
+            } else {
+                user::handle(userid, login, school, logout, switch, cache_dir, &args)
+            }
+        }
+
+        Command::Cache { action, all_users } => {
+            let userids = if all_users {
+                CONFIG.users.iter().map(|u| u.userid.clone()).collect()
+            } else if let Some(who) = args.user.as_ref() {
+                vec![
+                    CONFIG
+                        .get_userid(who)
+                        .ok_or(format!("invalid user ({who}) specified"))?,
+                ]
+            } else if CONFIG.default_userid.is_empty() {
+                // would be the cache root, shared by all users
+                return Err(
+                    "no user selected, please log in with `rsfilc user --login` or use `--user`"
+                        .into(),
+                );
+            } else {
+                vec![CONFIG.default_userid.clone()]
+            };
+            cache::handle(&userids, action.unwrap_or(args::CacheAction::Status), &args)
+        }
+
+        Command::Backup { file, no_tokens } => backup::backup(&file, !no_tokens),
+        Command::Restore { file, force } => backup::restore(&file, force),
 
         Command::Schools { search } => schools::handle(search, &args),
 
@@ -127,10 +226,7 @@ fn run(args: Args) -> Res<()> {
 }
 
 fn set_up_logger(verbosity: LevelFilter) -> Res<()> {
-    let path = paths::cache_dir("")
-        .ok_or("no cache dir")?
-        .join(config::APP_NAME)
-        .with_extension("log");
+    let path = paths::log_path().ok_or("no cache dir")?;
     fern::Dispatch::new()
         .format(|out, message, record| {
             out.finish(format_args!(
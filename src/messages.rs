@@ -1,22 +1,24 @@
 //! messages from teachers and staff
 
+use crate::config::{APP_NAME, BUILTIN_RENDERER, CONFIG};
 use crate::{paths::download_dir, time::MyDate, user::User, utils};
-use ekreta::{Endpoint, Res};
-use std::{char, fmt::Write};
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use ekreta::{Endpoint, MsgItem, Res};
+use std::path::{Path, PathBuf};
+use std::{char, fmt::Write, fs};
 
-pub fn handle_note_msgs(user: &User, id: Option<isize>, args: &crate::Args) -> Res<()> {
+pub fn handle_note_msgs(
+    user: &User,
+    id: Option<isize>,
+    raw_html: bool,
+    args: &crate::Args,
+) -> Res<()> {
     let notes = user.get_note_msgs((None, None))?;
     if let Some(ix) = id_to_ix(id, notes.len()) {
         let Some(nm) = notes.get(ix) else {
             return Err(format!("can't find message with id: {ix}").into());
         };
-        let print = if args.machine {
-            serde_json::to_string(nm)?
-        } else {
-            disp_nm(nm)
-        };
-        println!("{print}");
-        return Ok(());
+        return show_nm(nm, raw_html, args);
     }
 
     let data = notes.iter().enumerate().collect::<Vec<_>>();
@@ -26,20 +28,19 @@ pub fn handle_note_msgs(user: &User, id: Option<isize>, args: &crate::Args) -> R
     utils::print_table(&data, headers, args.reverse, args.number, disp)
 }
 
-pub fn handle(user: &User, id: Option<isize>, args: &crate::Args) -> Res<()> {
+pub fn handle(
+    user: &User,
+    id: Option<isize>,
+    open: bool,
+    raw_html: bool,
+    args: &crate::Args,
+) -> Res<()> {
     let msg_oviews = user.get_msg_oviews()?;
     if let Some(ix) = id_to_ix(id, msg_oviews.len()) {
         let msg_oview = msg_oviews
             .get(ix)
             .ok_or(format!("can't find message with id: {ix}"))?;
-        let msg = user.get_msg(msg_oview)?;
-        let print = if args.machine {
-            serde_json::to_string(&msg)?
-        } else {
-            disp_msg(&msg)
-        };
-        println!("{print}");
-        return Ok(());
+        return show_msg(user, msg_oview, open, raw_html, args);
     }
 
     let data = msg_oviews.iter().enumerate().collect::<Vec<_>>();
@@ -49,6 +50,99 @@ pub fn handle(user: &User, id: Option<isize>, args: &crate::Args) -> Res<()> {
     utils::print_table(&data, headers, args.reverse, args.number, disp)
 }
 
+/// messages and note messages together, in the order they were sent
+pub fn handle_all(
+    user: &User,
+    id: Option<isize>,
+    open: bool,
+    raw_html: bool,
+    args: &crate::Args,
+) -> Res<()> {
+    let msg_oviews = user.get_msg_oviews()?;
+    let notes = user.get_note_msgs((None, None))?;
+    let msgs = msg_oviews.iter().map(AnyMsg::Msg);
+    let mut all = msgs
+        .chain(notes.iter().map(AnyMsg::Note))
+        .collect::<Vec<_>>();
+    all.sort_by_key(AnyMsg::when);
+    if let Some(ix) = id_to_ix(id, all.len()) {
+        return match all.get(ix) {
+            Some(AnyMsg::Msg(msg_oview)) => show_msg(user, msg_oview, open, raw_html, args),
+            Some(AnyMsg::Note(nm)) => {
+                if open {
+                    eprintln!("note messages have no attachments to open");
+                }
+                show_nm(nm, raw_html, args)
+            }
+            None => Err(format!("can't find message with id: {ix}").into()),
+        };
+    }
+
+    let data = all.into_iter().enumerate().collect::<Vec<_>>();
+    let headers = ["ID", "FAJTA", "TÁRGYA", "TŐLE", "EKKOR", "CSATOLMÁNYA"].iter();
+    #[rustfmt::skip]
+    let disp = if args.machine { None } else { Some(preview_any) };
+    utils::print_table(&data, headers, args.reverse, args.number, disp)
+}
+
+/// either kind of message, so that they can be listed together
+#[derive(serde::Serialize)]
+#[serde(tag = "kind", content = "item", rename_all = "snake_case")]
+enum AnyMsg<'a> {
+    Msg(&'a ekreta::MsgOview),
+    Note(&'a ekreta::NoteMsg),
+}
+impl AnyMsg<'_> {
+    fn when(&self) -> ekreta::LDateTime {
+        match self {
+            AnyMsg::Msg(msg_oview) => msg_oview.uzenet_kuldes_datum,
+            AnyMsg::Note(note_msg) => note_msg.datum,
+        }
+    }
+}
+
+fn preview_any(preview: &(usize, AnyMsg)) -> Vec<String> {
+    let (mut row, kind) = match preview.1 {
+        AnyMsg::Msg(msg_oview) => (disp_oviews(&(preview.0, msg_oview)), "üzenet"),
+        AnyMsg::Note(note_msg) => (preview_nm(&(preview.0, note_msg)), "feljegyzés"),
+    };
+    row.insert(1, kind.to_string());
+    row
+}
+
+/// print a whole message, fetching it if needed
+fn show_msg(
+    user: &User,
+    msg_oview: &ekreta::MsgOview,
+    open: bool,
+    raw_html: bool,
+    args: &crate::Args,
+) -> Res<()> {
+    let msg = user.get_msg(msg_oview)?;
+    if raw_html {
+        println!("{}", msg.uzenet.szoveg);
+    } else if args.machine {
+        utils::print_json(&msg)?;
+    } else {
+        utils::print_paged(&disp_msg(&msg))?;
+    }
+    if open {
+        open_attachments(user, &msg)?;
+    }
+    Ok(())
+}
+
+fn show_nm(nm: &ekreta::NoteMsg, raw_html: bool, args: &crate::Args) -> Res<()> {
+    if raw_html {
+        println!("{}", nm.tartalom_formazott);
+    } else if args.machine {
+        utils::print_json(nm)?;
+    } else {
+        utils::print_paged(&disp_nm(nm))?;
+    }
+    Ok(())
+}
+
 /// make a `-id` reverse of id, eg len: 8, id: -1 -> ix = 7
 fn id_to_ix(id: Option<isize>, len: usize) -> Option<usize> {
     let id = id?;
@@ -77,22 +171,54 @@ fn disp_oviews(preview: &(usize, &ekreta::MsgOview)) -> Vec<String> {
     row
 }
 
-pub fn download_attachment_to(am: &ekreta::Attachment) -> std::path::PathBuf {
-    download_dir().join(am.fajl_nev.replace(char::is_whitespace, "_"))
+/// where to save `am` of `msg`: each message has its own directory,
+/// so that attachments with the same name won't overwrite each other
+pub fn download_attachment_to(msg: &ekreta::MsgItem, am: &ekreta::Attachment) -> PathBuf {
+    let file_name = am.fajl_nev.replace(char::is_whitespace, "_");
+    download_dir()
+        .join(msg.azonosito.to_string())
+        .join(file_name)
+}
+
+/// download (if not yet done) and open all attachments of `msg` with the system's default app
+fn open_attachments(user: &User, msg: &ekreta::MsgItem) -> Res<()> {
+    if msg.uzenet.csatolmanyok.is_empty() {
+        eprintln!("this message has no attachments to open");
+        return Ok(());
+    }
+    user.download_attachments(msg)?;
+    for am in &msg.uzenet.csatolmanyok {
+        utils::open_externally(&download_attachment_to(msg, am))?;
+    }
+    Ok(())
+}
+
+/// render `html` with the first renderer of [`CONFIG`] that works, falling back to the builtin one
+pub fn render_html(html: &str) -> String {
+    for renderer in &CONFIG.renderers {
+        if renderer == BUILTIN_RENDERER {
+            break;
+        }
+        match utils::pipe_through(renderer, html) {
+            Ok(rendered) => return rendered,
+            Err(e) => log::warn!("couldn't render html with {renderer:?}: {e}"),
+        }
+    }
+    nanohtml2text::html2text(html)
 }
 
 pub fn disp_msg(msg: &ekreta::MsgItem) -> String {
     let mut f = String::new();
     _ = writeln!(&mut f, "| Tárgy: {}", msg.uzenet.targy);
     for am in &msg.uzenet.csatolmanyok {
-        let out_path = download_attachment_to(am);
+        let out_path = download_attachment_to(msg, am);
         _ = writeln!(&mut f, "| Csatolmány: \"file://{}\"", out_path.display());
     }
     let name = &msg.tipus.nev;
     _ = writeln!(&mut f, "| {name}: {}", msg.when().unwrap().pretty());
     let sender = &msg.uzenet.felado_nev;
     _ = writeln!(&mut f, "| Feladó: {sender} {}", msg.uzenet.felado_titulus);
-    let rendered = nanohtml2text::html2text(&msg.uzenet.szoveg);
+    let rendered = render_html(&msg.uzenet.szoveg);
     _ = write!(&mut f, "\n{rendered}");
     // if !msg.is_elolvasva {
     //  writeln!(f, "Olvasatlan")?;
@@ -115,7 +241,172 @@ pub fn disp_nm(note_msg: &ekreta::NoteMsg) -> String {
     _ = writeln!(&mut f, "| {}", note_msg.cim);
     _ = writeln!(&mut f, "| Időpont: {}", note_msg.datum.pretty());
     _ = writeln!(&mut f, "| {}", note_msg.keszito_tanar_neve);
-    let rendered = nanohtml2text::html2text(&note_msg.tartalom_formazott);
+    let rendered = render_html(&note_msg.tartalom_formazott);
     _ = write!(&mut f, "\n{rendered}");
     f
 }
+
+/// formats messages can be exported to
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// a markdown file for each message, attachments copied next to them
+    Md,
+    /// an `.eml` file for each message, attachments embedded
+    Eml,
+    /// a single mbox file with every message, attachments embedded
+    Mbox,
+}
+
+/// write all messages to `dir` in `format`, fetching the ones that aren't cached yet
+pub fn export(user: &User, dir: &Path, format: ExportFormat) -> Res<()> {
+    fs::create_dir_all(dir)?;
+    let msg_oviews = user.get_msg_oviews()?;
+    let mut mbox = String::new();
+    let mut exported = 0;
+    for oview in &msg_oviews {
+        let msg = match user.get_msg(oview) {
+            Ok(msg) => msg,
+            Err(e) => {
+                log::error!("couldn't export message {}: {e:?}", oview.azonosito);
+                eprintln!("skipping message {}: {e}", oview.uzenet_targy);
+                continue;
+            }
+        };
+        // cached messages might have their attachments missing
+        if let Err(e) = user.download_attachments(&msg) {
+            log::warn!("couldn't download attachments of {}: {e:?}", msg.azonosito);
+        }
+        let date = msg.uzenet.kuldes_datum.format("%Y-%m-%d");
+        let name = format!("{date}_{}", msg.azonosito);
+        match format {
+            ExportFormat::Md => {
+                let md = to_markdown(&msg, &dir.join(&name))?;
+                fs::write(dir.join(name).with_extension("md"), md)?;
+            }
+            ExportFormat::Eml => fs::write(dir.join(name).with_extension("eml"), to_eml(&msg))?,
+            ExportFormat::Mbox => append_to_mbox(&mut mbox, &msg),
+        }
+        exported += 1;
+    }
+    if format == ExportFormat::Mbox {
+        fs::write(dir.join(APP_NAME).with_extension("mbox"), mbox)?;
+    }
+    println!("exported {exported} messages to {}", dir.display());
+    Ok(())
+}
+
+/// markdown of `msg`, its attachments are copied to `attachment_dir` and linked relatively
+fn to_markdown(msg: &MsgItem, attachment_dir: &Path) -> Res<String> {
+    let mut f = String::new();
+    let uzenet = &msg.uzenet;
+    _ = writeln!(&mut f, "# {}\n", uzenet.targy);
+    _ = writeln!(
+        &mut f,
+        "- Feladó: {} {}",
+        uzenet.felado_nev, uzenet.felado_titulus
+    );
+    _ = writeln!(
+        &mut f,
+        "- Dátum: {}",
+        uzenet.kuldes_datum.format("%Y-%m-%d %H:%M")
+    );
+    _ = writeln!(&mut f, "- Típus: {}", msg.tipus.nev);
+    for am in &uzenet.csatolmanyok {
+        let downloaded = download_attachment_to(msg, am);
+        let Some(file_name) = downloaded.file_name() else {
+            continue;
+        };
+        if !downloaded.exists() {
+            _ = writeln!(&mut f, "- Csatolmány (nem letöltött): {}", am.fajl_nev);
+            continue;
+        }
+        fs::create_dir_all(attachment_dir)?;
+        fs::copy(&downloaded, attachment_dir.join(file_name))?;
+        let dir_name = attachment_dir.file_name().unwrap_or_default().display();
+        let link = format!("{dir_name}/{}", file_name.display());
+        _ = writeln!(&mut f, "- Csatolmány: [{}](<{link}>)", am.fajl_nev);
+    }
+    _ = write!(&mut f, "\n{}", render_html(&uzenet.szoveg));
+    Ok(f)
+}
+
+/// domain for made-up addresses, as E-Kréta doesn't give us real ones
+const MAIL_DOMAIN: &str = "kreta.invalid";
+
+/// `msg` as a MIME message: html body, attachments embedded, metadata in headers
+fn to_eml(msg: &MsgItem) -> String {
+    let uzenet = &msg.uzenet;
+    let boundary = format!("{APP_NAME}-{}", msg.azonosito);
+    let sender = format!("{} {}", uzenet.felado_nev, uzenet.felado_titulus);
+    let mut f = String::new();
+    _ = writeln!(
+        &mut f,
+        "From: {} <noreply@{MAIL_DOMAIN}>",
+        mime_word(&sender)
+    );
+    _ = writeln!(&mut f, "Subject: {}", mime_word(&uzenet.targy));
+    _ = writeln!(&mut f, "Date: {}", uzenet.kuldes_datum.to_rfc2822());
+    _ = writeln!(&mut f, "Message-ID: <{}@{MAIL_DOMAIN}>", msg.azonosito);
+    _ = writeln!(&mut f, "X-Kreta-Tipus: {}", mime_word(&msg.tipus.nev));
+    _ = writeln!(&mut f, "MIME-Version: 1.0");
+    _ = writeln!(
+        &mut f,
+        "Content-Type: multipart/mixed; boundary=\"{boundary}\"\n"
+    );
+
+    _ = writeln!(&mut f, "--{boundary}");
+    _ = writeln!(&mut f, "Content-Type: text/html; charset=utf-8");
+    _ = writeln!(&mut f, "Content-Transfer-Encoding: base64\n");
+    _ = writeln!(&mut f, "{}", base64_lines(uzenet.szoveg.as_bytes()));
+    for am in &uzenet.csatolmanyok {
+        let Ok(content) = fs::read(download_attachment_to(msg, am)) else {
+            log::warn!("attachment {} isn't downloaded, not embedding", am.fajl_nev);
+            continue;
+        };
+        let name = mime_word(&am.fajl_nev);
+        _ = writeln!(&mut f, "--{boundary}");
+        _ = writeln!(
+            &mut f,
+            "Content-Type: application/octet-stream; name=\"{name}\""
+        );
+        _ = writeln!(
+            &mut f,
+            "Content-Disposition: attachment; filename=\"{name}\""
+        );
+        _ = writeln!(&mut f, "Content-Transfer-Encoding: base64\n");
+        _ = writeln!(&mut f, "{}", base64_lines(&content));
+    }
+    _ = writeln!(&mut f, "--{boundary}--");
+    f
+}
+
+/// append `msg` to `mbox` (mboxrd), readable by eg. mutt or Thunderbird
+fn append_to_mbox(mbox: &mut String, msg: &MsgItem) {
+    let asctime = msg.uzenet.kuldes_datum.format("%a %b %e %H:%M:%S %Y");
+    _ = writeln!(mbox, "From noreply@{MAIL_DOMAIN} {asctime}");
+    for line in to_eml(msg).lines() {
+        // escape lines that could be taken as the start of a new message
+        if line.trim_start_matches('>').starts_with("From ") {
+            mbox.push('>');
+        }
+        _ = writeln!(mbox, "{line}");
+    }
+    mbox.push('\n');
+}
+
+/// encode `text` as a MIME encoded-word if it isn't plain ascii
+fn mime_word(text: &str) -> String {
+    if text.is_ascii() {
+        return text.to_owned();
+    }
+    format!("=?UTF-8?B?{}?=", BASE64.encode(text))
+}
+
+/// base64 of `bytes`, broken into lines as MIME requires
+fn base64_lines(bytes: &[u8]) -> String {
+    let encoded = BASE64.encode(bytes);
+    let lines = encoded.as_bytes().chunks(76);
+    // SAFETY: base64 is ascii
+    let lines = lines.map(|l| std::str::from_utf8(l).unwrap_or_default());
+    lines.collect::<Vec<_>>().join("\n")
+}
@@ -1,4 +1,4 @@
-use crate::config::APP_NAME;
+use crate::config::{APP_NAME, CONFIG, Config};
 use std::{fs, path::PathBuf};
 
 /// get path for cache dir, create if doesn't exist
@@ -18,15 +18,26 @@ pub fn cache_path(userid: &str, kind: &str) -> Option<PathBuf> {
     Some(cache_dir.join(format!("{kind}_cache.jsonc")))
 }
 
-/// get path for `Downloads/rsfilc`, and create it if doesn't exist yet
+/// get path for the log, in the root of the cache dir
+pub fn log_path() -> Option<PathBuf> {
+    Some(cache_dir("")?.join(APP_NAME).with_extension("log"))
+}
+
+/// get path for the configured download dir or `Downloads/rsfilc`, and create it if doesn't exist yet
 ///
 /// # Panics
 ///
 /// no `Downloads`
 pub fn download_dir() -> PathBuf {
-    let dl_dir = dirs::download_dir()
-        .unwrap_or_else(|| dirs::home_dir().expect("no home dir").join("Downloads"))
-        .join(APP_NAME);
+    download_dir_of(&CONFIG)
+}
+/// [`download_dir`], as configured in `conf`
+pub fn download_dir_of(conf: &Config) -> PathBuf {
+    let dl_dir = conf.download_dir.clone().unwrap_or_else(|| {
+        dirs::download_dir()
+            .unwrap_or_else(|| dirs::home_dir().expect("no home dir").join("Downloads"))
+            .join(APP_NAME)
+    });
     if !dl_dir.exists() {
         fs::create_dir_all(&dl_dir).unwrap();
     }
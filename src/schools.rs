@@ -23,6 +23,9 @@ pub fn get(q: &str) -> Res<Vec<ekreta::School>> {
         }
     }
 
+    if crate::args::opts().offline {
+        return Err("offline, and no cached school matches".into());
+    }
     let schools = ekreta::School::fetch_schools(q)?;
     cached_schools.extend(schools.clone());
     cached_schools.dedup();
@@ -0,0 +1,234 @@
+//! keeping the [`Token`] secret: in the OS keyring if available, otherwise encrypted with a passphrase
+
+use crate::{Res, cache};
+use argon2::Argon2;
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use chacha20poly1305::{ChaCha20Poly1305, Key, KeyInit, Nonce, aead::Aead};
+use ekreta::{LDateTime, Token};
+use inquire::{Password, PasswordDisplayMode};
+use serde::{Deserialize, Serialize};
+use std::{io::IsTerminal, sync::OnceLock};
+
+/// env var to read the passphrase from, eg. on headless machines without a keyring
+pub const PASSPHRASE_VAR: &str = "RSFILC_PASSPHRASE";
+/// the [`Token`] is still cached as this kind, just not in plaintext
+const KIND: &str = "token";
+
+/// what's actually in the token cache
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Stored {
+    /// the token is in the OS keyring
+    Keyring { keyring: bool },
+    /// encrypted with a key derived from a passphrase, all base64
+    Encrypted {
+        salt: String,
+        nonce: String,
+        ciphertext: String,
+    },
+    /// what we used to do, migrated on load if there's a keyring or a passphrase
+    Plain(Token),
+}
+
+/// save `token` of `userid` as securely as possible
+pub fn store(userid: &str, token: &Token) -> Res<()> {
+    store_as_of(userid, token, chrono::Local::now())
+}
+fn store_as_of(userid: &str, token: &Token, cached_at: LDateTime) -> Res<()> {
+    let Some(stored) = secure(userid, token)? else {
+        log::warn!("no keyring, nor passphrase, not storing token");
+        let e = format!("no keyring available, set `{PASSPHRASE_VAR}` to encrypt the token");
+        return Err(format!("{e}, won't store it in plaintext").into());
+    };
+    cache::store_as_of(userid, KIND, &serde_json::to_string(&stored)?, cached_at)
+}
+/// put `token` in the keyring, or encrypt it, `None` if neither is possible
+fn secure(userid: &str, token: &Token) -> Res<Option<Stored>> {
+    let json = serde_json::to_string(token)?;
+    if os_keyring::set(userid, &json) {
+        Ok(Some(Stored::Keyring { keyring: true }))
+    } else if let Some(passphrase) = passphrase() {
+        Ok(Some(encrypt(&json, passphrase)?))
+    } else {
+        Ok(None)
+    }
+}
+
+/// load the token of `userid`, migrating a plaintext one
+pub fn load(userid: &str) -> Option<(LDateTime, Token)> {
+    let (cached_at, content) = cache::load(userid, KIND)?;
+    let stored = serde_json::from_str(&content)
+        .inspect_err(|e| log::error!("couldn't deserialize token: {e:?}"))
+        .ok()?;
+    let token = match stored {
+        Stored::Keyring { .. } => {
+            let json = os_keyring::get(userid)?;
+            serde_json::from_str(&json).ok()?
+        }
+        Stored::Encrypted {
+            salt,
+            nonce,
+            ciphertext,
+        } => {
+            let json = decrypt(&salt, &nonce, &ciphertext, passphrase()?)
+                .inspect_err(|e| {
+                    log::error!("couldn't decrypt token: {e}");
+                    eprintln!("couldn't decrypt token, wrong passphrase?");
+                })
+                .ok()?;
+            serde_json::from_str(&json).ok()?
+        }
+        Stored::Plain(token) => {
+            log::info!("migrating plaintext token");
+            match secure(userid, &token) {
+                Ok(Some(stored)) => {
+                    let stored = serde_json::to_string(&stored).ok()?;
+                    if let Err(e) = cache::store_as_of(userid, KIND, &stored, cached_at) {
+                        log::error!("couldn't migrate plaintext token: {e:?}");
+                    }
+                }
+                Ok(None) => log::warn!("plaintext token, set `{PASSPHRASE_VAR}` to migrate it"),
+                Err(e) => log::error!("couldn't migrate plaintext token: {e:?}"),
+            }
+            token
+        }
+    };
+    Some((cached_at, token))
+}
+
+/// the token cache of `userid` with the token taken out of the keyring, encrypted, eg. for a backup
+///
+/// `None` if the token isn't in the keyring
+pub fn export(userid: &str) -> Res<Option<String>> {
+    let Some((cached_at, content)) = cache::load(userid, KIND) else {
+        return Ok(None);
+    };
+    let Stored::Keyring { .. } = serde_json::from_str(&content)? else {
+        return Ok(None);
+    };
+    let json = os_keyring::get(userid).ok_or("couldn't get it from the keyring")?;
+    let passphrase = passphrase().ok_or(format!(
+        "it's in the keyring, set `{PASSPHRASE_VAR}` to export it encrypted"
+    ))?;
+    let stored = encrypt(&json, passphrase)?;
+    Ok(Some(cache::file_content(
+        &serde_json::to_string(&stored)?,
+        cached_at,
+    )))
+}
+
+/// remove the token of `userid` from the keyring, the cache is deleted with the rest
+pub fn forget(userid: &str) {
+    os_keyring::forget(userid);
+}
+
+/// the OS keyring, only where `keyring` has a real store
+#[cfg(all(
+    feature = "keyring",
+    any(target_os = "linux", target_os = "macos", target_os = "windows")
+))]
+mod os_keyring {
+    use keyring::{Entry, mock::MockCredential};
+
+    /// `None` if there's only the mock store, that'd forget everything once we exit
+    fn entry(userid: &str) -> Option<Entry> {
+        let entry = Entry::new(crate::config::APP_NAME, userid)
+            .inspect_err(|e| log::warn!("keyring unavailable: {e}"))
+            .ok()?;
+        if entry.get_credential().is::<MockCredential>() {
+            log::warn!("keyring unavailable: only a mock store");
+            return None;
+        }
+        Some(entry)
+    }
+
+    pub fn forget(userid: &str) {
+        let Some(entry) = entry(userid) else { return };
+        if let Err(e) = entry.delete_credential() {
+            log::info!("couldn't delete token from keyring: {e}");
+        }
+    }
+    pub fn set(userid: &str, secret: &str) -> bool {
+        entry(userid).is_some_and(|entry| {
+            entry
+                .set_password(secret)
+                .inspect_err(|e| log::warn!("keyring unavailable: {e}"))
+                .is_ok()
+        })
+    }
+    pub fn get(userid: &str) -> Option<String> {
+        entry(userid)?
+            .get_password()
+            .inspect_err(|e| log::error!("couldn't get token from keyring: {e}"))
+            .ok()
+    }
+}
+#[cfg(not(all(
+    feature = "keyring",
+    any(target_os = "linux", target_os = "macos", target_os = "windows")
+)))]
+mod os_keyring {
+    pub fn forget(userid: &str) {
+        log::info!("no keyring support, nothing to forget for {userid}");
+    }
+    pub fn set(_userid: &str, _secret: &str) -> bool {
+        false
+    }
+    pub fn get(_userid: &str) -> Option<String> {
+        log::error!("token is in the keyring, but built without keyring support");
+        None
+    }
+}
+
+/// from [`PASSPHRASE_VAR`], or asked for once, if there's a terminal to ask on
+fn passphrase() -> Option<&'static str> {
+    static PASSPHRASE: OnceLock<Option<String>> = OnceLock::new();
+    let get = || {
+        if let Ok(passphrase) = std::env::var(PASSPHRASE_VAR) {
+            return Some(passphrase);
+        }
+        if crate::args::opts().non_interactive || !std::io::stdin().is_terminal() {
+            return None;
+        }
+        Password::new("passphrase for the token:")
+            .with_display_mode(PasswordDisplayMode::Masked)
+            .without_confirmation()
+            .prompt()
+            .ok()
+    };
+    PASSPHRASE.get_or_init(get).as_deref()
+}
+
+fn cipher(passphrase: &str, salt: &[u8]) -> Res<ChaCha20Poly1305> {
+    let mut key = [0; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("couldn't derive key: {e}"))?;
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+fn encrypt(plain: &str, passphrase: &str) -> Res<Stored> {
+    let (mut salt, mut nonce) = ([0; 16], [0; 12]);
+    getrandom::fill(&mut salt).map_err(|e| format!("no randomness: {e}"))?;
+    getrandom::fill(&mut nonce).map_err(|e| format!("no randomness: {e}"))?;
+    let ciphertext = cipher(passphrase, &salt)?
+        .encrypt(Nonce::from_slice(&nonce), plain.as_bytes())
+        .map_err(|e| format!("couldn't encrypt: {e}"))?;
+    Ok(Stored::Encrypted {
+        salt: BASE64.encode(salt),
+        nonce: BASE64.encode(nonce),
+        ciphertext: BASE64.encode(ciphertext),
+    })
+}
+fn decrypt(salt: &str, nonce: &str, ciphertext: &str, passphrase: &str) -> Res<String> {
+    let nonce = BASE64.decode(nonce)?;
+    if nonce.len() != 12 {
+        return Err("invalid nonce".into());
+    }
+    let plain = cipher(passphrase, &BASE64.decode(salt)?)?
+        .decrypt(
+            Nonce::from_slice(&nonce),
+            BASE64.decode(ciphertext)?.as_slice(),
+        )
+        .map_err(|e| format!("couldn't decrypt: {e}"))?;
+    Ok(String::from_utf8(plain)?)
+}
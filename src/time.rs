@@ -1,4 +1,4 @@
-use chrono::{Datelike, Local, NaiveDate, Timelike};
+use chrono::{Datelike, Local, NaiveDate, TimeDelta, Timelike};
 use ekreta::LDateTime;
 
 /// `DateTime` methods needed for `RsFilc`
@@ -86,3 +86,33 @@ impl MyDate for NaiveDate {
         }
     }
 }
+
+/// how long ago `t` was, eg. "3 órája"
+pub fn ago(t: &LDateTime) -> String {
+    let since = Local::now() - t;
+    if since.num_days() > 0 {
+        format!("{} napja", since.num_days())
+    } else if since.num_hours() > 0 {
+        format!("{} órája", since.num_hours())
+    } else {
+        format!("{} perce", since.num_minutes())
+    }
+}
+
+/// parse an age like "30s", "10m", "2h" or "1d"
+pub fn parse_age(age: &str) -> Result<TimeDelta, String> {
+    let age = age.trim();
+    let unit_at = age.len().saturating_sub(1);
+    let (num, unit) = (age.get(..unit_at), age.get(unit_at..));
+    let num = num.and_then(|n| n.parse::<i64>().ok()).filter(|n| *n >= 0);
+    let invalid = || format!("invalid age: {age:?}, expected eg. 30s, 10m, 2h or 1d");
+    let num = num.ok_or_else(invalid)?;
+    match unit {
+        Some("s") => TimeDelta::try_seconds(num),
+        Some("m") => TimeDelta::try_minutes(num),
+        Some("h") => TimeDelta::try_hours(num),
+        Some("d") => TimeDelta::try_days(num),
+        _ => None,
+    }
+    .ok_or_else(invalid)
+}
@@ -2,7 +2,7 @@
 
 use crate::{time::MyDate, user::User, utils};
 use chrono::{Datelike, Local, NaiveDate, TimeDelta};
-use ekreta::{AnnouncedTest, Lesson, Res};
+use ekreta::{AnnouncedTest, HomeWork, Lesson, Res};
 use log::*;
 use yansi::Paint;
 
@@ -17,16 +17,14 @@ pub fn handle(day: NaiveDate, user: &User, current: bool, week: bool, json: bool
     if current {
         if let Some(nxt) = next_lesson(&lessons_of_week) {
             if json {
-                let data = serde_json::to_string(&(nxt.mins_till_start(), nxt))?;
-                println!("{data}");
+                utils::print_json(&(nxt.mins_till_start(), nxt))?;
             } else {
                 println!("{}m -> {}", nxt.mins_till_start(), nxt.nev);
             }
         }
         for cnt_lsn in current_lessons(&lessons) {
             if json {
-                let data = serde_json::to_string(&(cnt_lsn.mins_till_end(), cnt_lsn))?;
-                println!("{data}");
+                utils::print_json(&(cnt_lsn.mins_till_end(), cnt_lsn))?;
             } else {
                 println!("{}, {}m", cnt_lsn.nev, cnt_lsn.mins_till_end());
             }
@@ -35,13 +33,15 @@ pub fn handle(day: NaiveDate, user: &User, current: bool, week: bool, json: bool
     }
     if json {
         let print_lsns = if week { lessons_of_week } else { lessons };
-        let json = serde_json::to_string(&print_lsns)?;
-        println!("{json}");
+        utils::print_json(&print_lsns)?;
     } else if week {
         print_week(lessons_of_week);
     } else {
         let tests = user.get_tests((Some(day), Some(day))).unwrap_or_default();
-        print_day(lessons, &tests);
+        let homework = user
+            .get_homework((Some(day), Some(day)))
+            .unwrap_or_default();
+        print_day(lessons, &tests, &homework);
     }
 
     Ok(())
@@ -97,8 +97,22 @@ fn ignore_lesson(lsn: &Lesson) -> bool {
     lsn.kamu_smafu() || lsn.cancelled()
 }
 
+/// whether `hw` was given on `lsn`
+pub fn given_on(hw: &HomeWork, lsn: &Lesson) -> bool {
+    hw.feladas_datuma.date_naive() == lsn.date_naive()
+        && lsn
+            .tantargy
+            .as_ref()
+            .is_some_and(|s| s.nev == hw.tantargy_neve)
+}
+
 /// you may want to check `lsn` validity: `lsn.kamu_smafu()`
-pub fn disp(lsn: &Lesson, nxt_lsn: Option<&Lesson>, test: Option<&AnnouncedTest>) -> Vec<String> {
+pub fn disp(
+    lsn: &Lesson,
+    nxt_lsn: Option<&Lesson>,
+    test: Option<&AnnouncedTest>,
+    homework: Option<&HomeWork>,
+) -> Vec<String> {
     let topic = lsn
         .tema
         .as_ref()
@@ -145,12 +159,17 @@ pub fn disp(lsn: &Lesson, nxt_lsn: Option<&Lesson>, test: Option<&AnnouncedTest>
         let test = format!("{}{}", existing_test.modja.leiras.bold(), topic);
         row.push(test);
     }
+    if let Some(hw) = homework {
+        let task = crate::homework::task(hw);
+        let first_line = task.lines().next().unwrap_or_default();
+        row.push(format!("{}: {}", "házi".bold(), first_line.italic()));
+    }
 
     row
 }
 
 /// print all lessons of a day
-pub fn print_day(mut lessons: Vec<Lesson>, tests: &[AnnouncedTest]) {
+pub fn print_day(mut lessons: Vec<Lesson>, tests: &[AnnouncedTest], homework: &[HomeWork]) {
     let Some(first_lesson) = lessons.first() else {
         warn!("empty lesson-list got, won't print");
         return;
@@ -173,7 +192,8 @@ pub fn print_day(mut lessons: Vec<Lesson>, tests: &[AnnouncedTest]) {
         let h_ix = usize::from(lsn.d_num() - day_start); // hour index
 
         let same_n = |t: &&AnnouncedTest| t.orarendi_ora_oraszama == lsn.oraszam;
-        let disp = disp(&lsn, nxt_lsn.as_ref(), tests.iter().find(same_n));
+        let hw = homework.iter().find(|hw| given_on(hw, &lsn));
+        let disp = disp(&lsn, nxt_lsn.as_ref(), tests.iter().find(same_n), hw);
         data[h_ix] = (0..data[h_ix].len().max(disp.len())) // manual impl of `itertools::zip_longest()`
             .map(|i| (data[h_ix].get(i), disp.get(i)))
             .map(|(orig, new)| match [orig, new] {
@@ -187,7 +207,7 @@ pub fn print_day(mut lessons: Vec<Lesson>, tests: &[AnnouncedTest]) {
     }
 
     #[rustfmt::skip]
-    utils::print_table_wh([".", "EKKOR", "ÓRA", "TEREM", "TANÁR", "EXTRA", "EXTRA-EXTRA"], data);
+    utils::print_table_wh([".", "EKKOR", "ÓRA", "TEREM", "TANÁR", "EXTRA", "EXTRA-EXTRA", "EXTRA-EXTRA-EXTRA"], data);
 }
 
 /// print week timetable
@@ -1,16 +1,18 @@
 use crate::{config::Config, *};
 use chrono::{Datelike, Local, NaiveDate, TimeDelta};
 use ekreta::{
-    Absence, Account, AnnouncedTest as Ancd, Evaluation as Eval, HeaderMap, LDateTime, Lesson,
-    MsgItem, MsgOview, OptIrval, Token, consts, header,
+    Absence, Account, AnnouncedTest as Ancd, Evaluation as Eval, HeaderMap, HomeWork, LDateTime,
+    Lesson, MsgItem, MsgOview, OptIrval, Token, consts, header,
 };
 use inquire::{Password, PasswordDisplayMode, Select};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::BTreeMap;
+use yansi::Paint;
 
 pub fn handle(
     userid: Option<String>,
     login: bool,
+    school: Option<String>,
     logout: bool,
     switch: bool,
     cache_dir: bool,
@@ -27,7 +29,7 @@ pub fn handle(
     };
     let mut conf = CONFIG.clone(); // will mutate, don't use plain CONFIG afterwards
     if login {
-        let res = User::login(name.clone(), &mut conf).inspect_err(|_| {
+        let res = User::login(name.clone(), school, &mut conf).inspect_err(|_| {
             eprintln!(
                 "couldn't log in to user account, check your credentials and connection with Kréta"
             );
@@ -59,6 +61,138 @@ pub fn handle(
     conf.save()
 }
 
+/// `--status`, `--refresh` or `--revoke` of `userid`, `--status` of all users if not specified
+///
+/// `--status` doesn't reach out to the server, unless `--refresh` is given as well
+pub fn handle_session(
+    userid: Option<String>,
+    status: bool,
+    refresh: bool,
+    revoke: bool,
+    args: &crate::Args,
+) -> Res<()> {
+    let users = if let Some(name) = userid {
+        vec![User::load(&CONFIG, &name).ok_or("the given userid/name isn't saved")?]
+    } else if (refresh && !status) || revoke {
+        let def_userid = &CONFIG.default_userid;
+        vec![User::load(&CONFIG, def_userid).ok_or("no user found, please log in")?]
+    } else {
+        CONFIG.users.iter().cloned().collect()
+    };
+    if revoke {
+        for user in &users {
+            cache::clear(&user.userid, Some("token"))?;
+        }
+        println!("tokens discarded");
+        return Ok(());
+    }
+    if refresh && !status {
+        for user in &users {
+            let (_, cached) = secret::load(&user.userid).ok_or("no cached token to refresh")?;
+            user.refresh(&cached)?;
+        }
+        println!("refreshed");
+        return Ok(());
+    }
+    let sessions = users
+        .iter()
+        .map(|u| Session::of(u, refresh))
+        .collect::<Vec<_>>();
+    if args.machine {
+        return utils::print_json(&sessions);
+    }
+    let mut headers = vec!["FELHASZNÁLÓ", "TOKEN", "LEJÁR"];
+    if refresh {
+        headers.push("FRISSÍTÉS");
+    }
+    utils::print_table_wh(headers, sessions.iter().map(Session::display).collect());
+    let synced_at = |t: &LDateTime| format!("{} ({})", t.pretty(), time::ago(t));
+    let synced = sessions.iter().flat_map(|s| {
+        let userid = &s.userid;
+        let kinds = s.synced.iter();
+        kinds.map(move |(kind, t)| vec![userid.clone(), kind.clone(), synced_at(t)])
+    });
+    utils::print_table_wh(["FELHASZNÁLÓ", "FAJTA", "SZINKRONIZÁLVA"], synced.collect());
+    Ok(())
+}
+
+/// state of the session of a [`User`]
+#[derive(Debug, Serialize)]
+struct Session {
+    userid: String,
+    token_cached: bool,
+    /// of the access token
+    expires_at: Option<LDateTime>,
+    refresh: Refresh,
+    /// what went wrong if [`Refresh::Failed`]
+    refresh_error: Option<String>,
+    /// kind of data: last successful sync
+    synced: BTreeMap<String, LDateTime>,
+}
+/// outcome of trying to refresh the token for `--status`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Refresh {
+    /// not asked for with `--refresh`
+    NotTried,
+    Ok,
+    Failed,
+    NoToken,
+}
+impl Session {
+    /// only reaches out to the server if `refresh`
+    fn of(user: &User, refresh: bool) -> Self {
+        let cached = secret::load(&user.userid);
+        let (refresh, refresh_error) = match &cached {
+            _ if !refresh => (Refresh::NotTried, None),
+            None => (Refresh::NoToken, None),
+            Some((_, token)) => match user.refresh(token) {
+                Ok(_) => (Refresh::Ok, None),
+                Err(e) => (Refresh::Failed, Some(e.to_string())),
+            },
+        };
+        // reloaded, as a successful refresh saved a new one
+        let expires_at = secret::load(&user.userid)
+            .map(|(cache_t, token)| cache_t + TimeDelta::seconds(token.expires_in.into()));
+        let synced = cache::synced(&user.userid).unwrap_or_default();
+        Self {
+            userid: user.userid.clone(),
+            token_cached: cached.is_some(),
+            expires_at,
+            refresh,
+            refresh_error,
+            synced,
+        }
+    }
+    fn display(&self) -> Vec<String> {
+        let token = if self.token_cached {
+            "van".green()
+        } else {
+            "nincs".red()
+        };
+        let expires_at = self.expires_at.map_or(String::new(), |t| {
+            let pretty = t.pretty();
+            if t < Local::now() {
+                format!("{pretty} (lejárt)")
+            } else {
+                pretty
+            }
+        });
+        let mut row = vec![self.userid.clone(), token.to_string(), expires_at];
+        let refresh = match self.refresh {
+            Refresh::NotTried => return row,
+            Refresh::Ok => "sikeres".green().to_string(),
+            Refresh::Failed => {
+                let e = self.refresh_error.as_deref().unwrap_or_default();
+                format!("hiba: {e}").red().to_string()
+            }
+            Refresh::NoToken => String::new(),
+        };
+        row.push(refresh);
+        row
+    }
+}
+
 /// Kréta, app user
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct User {
@@ -113,19 +247,27 @@ impl User {
     }
 
     /// log in to a [`User`] account from cli and write it to `conf`!
+    /// `schoolid` is asked for if `None`
     /// # Errors
     /// getting school-list, selecting school
-    pub fn login(userid: String, conf: &mut Config) -> Res<Self> {
+    pub fn login(userid: String, schoolid: Option<String>, conf: &mut Config) -> Res<Self> {
         info!("creating user ({userid}) from cli");
 
-        let _generating_cache = schools::get("e")?;
-        let school_q = inquire::Text::new("your school's name:").prompt()?;
-        let schools = schools::get(&school_q)?;
-        let items = schools.iter().map(|s| &s.nev).collect::<Vec<_>>();
-        let school_idx = Select::new("select the exact match:", items)
-            .raw_prompt()?
-            .index;
-        let schoolid = schools[school_idx].azonosito.clone();
+        let schoolid = if let Some(schoolid) = schoolid {
+            schoolid
+        } else {
+            if args::opts().non_interactive {
+                return Err(args::NeedsInteraction("school (`--school`)").into());
+            }
+            let _generating_cache = schools::get("e")?;
+            let school_q = inquire::Text::new("your school's name:").prompt()?;
+            let schools = schools::get(&school_q)?;
+            let items = schools.iter().map(|s| &s.nev).collect::<Vec<_>>();
+            let school_idx = Select::new("select the exact match:", items)
+                .raw_prompt()?
+                .index;
+            schools[school_idx].azonosito.clone()
+        };
         info!("received schoolid {schoolid} from cli");
 
         let user = Self::new(userid, schoolid, BTreeMap::new());
@@ -163,19 +305,30 @@ impl User {
     /// helper fn, loads cache of `kind` from `self.0.userid` cache-dir
     fn load_cache<D: for<'a> Deserialize<'a>>(&self) -> Option<(LDateTime, D)> {
         let kind = utils::type_to_kind_name::<D>().ok()?;
-        if std::env::var("NO_CACHE").is_ok_and(|nc| nc == "1") && kind != "token" {
+        let irreplaceable = cache::IRREPLACEABLE.contains(&kind.as_str());
+        if std::env::var("NO_CACHE").is_ok_and(|nc| nc == "1") && !irreplaceable {
             log::info!("ignoring cache: manually triggered 'no cache' error");
             eprintln!("ignoring cache: manually triggered 'no cache' error");
             return None;
         }
 
         let (cache_t, content) = cache::load(&self.userid, &kind)?;
-        let deserd = serde_json::from_str(&content)
-            .inspect_err(|e| {
+        let deserd = match serde_json::from_str(&content) {
+            Ok(deserd) => deserd,
+            Err(e) => {
                 error!("{e:?} - couldn't deserialize {kind}: {content}");
-                eprintln!("error: {e:?}, check logs with `cat $(rsfilc --cache-dir)/rsfilc.log`");
-            })
-            .ok()?;
+                let Some(lenient) = cache::lenient(&content) else {
+                    eprintln!(
+                        "error: {e:?}, check logs with `cat $(rsfilc --cache-dir)/rsfilc.log`"
+                    );
+                    cache::set_aside(&self.userid, &kind);
+                    return None;
+                };
+                // the partial one will overwrite it on the next store
+                cache::backup(&self.userid, &kind);
+                lenient
+            }
+        };
         Some((cache_t, deserd))
     }
     fn fetch_vec<E>(&self, query: E::Args) -> Res<Vec<E>>
@@ -186,6 +339,9 @@ impl User {
     }
     /// get headers which are necessary for making certain requests
     pub fn headers(&self) -> Res<HeaderMap> {
+        if args::opts().offline {
+            return Err(OFFLINE.into());
+        }
         let token = self.get_token()?.access_token;
         Ok(HeaderMap::from_iter([
             (header::AUTHORIZATION, format!("Bearer {token}").parse()?),
@@ -195,21 +351,23 @@ impl User {
 
     /// will read password from tty if there's no cached `Token`, otherwise uses `refresh_token`
     fn get_token(&self) -> Res<Token> {
-        if let Some((cache_t, cached_token)) = self.load_cache::<Token>() {
+        if let Some((cache_t, cached_token)) = secret::load(&self.userid) {
             if Local::now().signed_duration_since(cache_t)
                 < TimeDelta::seconds(cached_token.expires_in.into())
             {
                 return Ok(cached_token);
             }
-            // refresh token
-            let token = self.account.refresh_token(&cached_token.refresh_token)?;
-            self.store_cache(&token)?;
-            return Ok(token);
+            // unreachable server: don't ask for a password in vain, cached data may be shown instead
+            match self.refresh(&cached_token) {
+                Ok(token) => return Ok(token),
+                Err(e) if refresh_rejected(&*e) => {
+                    log::warn!("refresh token rejected, logging in again: {e}");
+                }
+                Err(e) => return Err(e),
+            }
         }
-        let password = Password::new("account password:")
-            .with_display_mode(PasswordDisplayMode::Masked)
-            .prompt()?;
-        info!("received password {} from cli", "*".repeat(password.len()));
+        let password = self.get_password()?;
+        info!("received password {}", "*".repeat(password.len()));
 
         let token = self
             .account
@@ -218,15 +376,56 @@ impl User {
                 log::error!("error fetching token: {e}");
                 eprintln!("error fetching token: {e}");
             })?;
-        self.store_cache(&token)?;
+        self.store_token(&token);
         info!("received token");
         Ok(token)
     }
+    /// get a new token using the `refresh_token` of `cached`, and save it
+    fn refresh(&self, cached: &Token) -> Res<Token> {
+        if args::opts().offline {
+            return Err(OFFLINE.into());
+        }
+        info!("refreshing token");
+        let token = self.account.refresh_token(&cached.refresh_token)?;
+        self.store_token(&token);
+        Ok(token)
+    }
+    /// save `token`, it's still usable for this run if that fails
+    fn store_token(&self, token: &Token) {
+        if let Err(e) = secret::store(&self.userid, token) {
+            log::error!("couldn't save token: {e}");
+            eprintln!("warning: couldn't save token, you'll need to log in again: {e}");
+        }
+    }
+    /// from stdin if `--password-stdin`, from `RSFILC_PASSWORD_COMMAND` if set, otherwise asked for
+    fn get_password(&self) -> Res<String> {
+        if args::opts().password_stdin {
+            info!("reading password from stdin");
+            let mut password = String::new();
+            std::io::stdin().read_line(&mut password)?;
+            return Ok(password.trim_end_matches(['\r', '\n']).to_string());
+        }
+        if let Ok(cmd) = std::env::var("RSFILC_PASSWORD_COMMAND") {
+            info!("getting password from {cmd:?}");
+            return password_from(&cmd, &self.userid);
+        }
+        if args::opts().non_interactive {
+            return Err(args::NeedsInteraction("account password").into());
+        }
+        Ok(Password::new("account password:")
+            .with_display_mode(PasswordDisplayMode::Masked)
+            .prompt()?)
+    }
     pub fn get_userinfo(&self) -> Res<ekreta::UserInfo> {
-        if let Some((_, cached_info)) = self.load_cache::<ekreta::UserInfo>()
-            && cached_info.next_downtime() + TimeDelta::hours(4) > Local::now()
-        {
-            return Ok(cached_info);
+        if let Some((cache_t, cached_info)) = self.load_cache::<ekreta::UserInfo>() {
+            let fresh = match cache::max_age("userinfo") {
+                Some(max_age) => Local::now() - cache_t < max_age,
+                // till the next downtime is surely over
+                None => cached_info.next_downtime() + TimeDelta::hours(4) > Local::now(),
+            };
+            if fresh {
+                return Ok(cached_info);
+            }
         }
         let fetched_info = self.account.fetch_info(&self.headers()?)?;
         self.store_cache(&fetched_info)?;
@@ -249,34 +448,41 @@ impl User {
         debug!("fetching tt, whole week: {whole_week}, from {from} to {to}");
 
         let (cache_t, cached_tt) = self.load_cache::<Vec<Lesson>>().unzip();
-        if let Some(lessons) = cached_tt.as_ref() {
-            let is_cached = |cl: &Lesson| cl.date_naive() == day;
-            let fresh_cache = |ct: LDateTime| (ct - Local::now()).abs() < TimeDelta::seconds(8);
-            if !whole_week && cache_t.is_some_and(fresh_cache) && lessons.iter().any(is_cached) {
-                warn!("warm lesson cache hit (< 8s), using instead of refetching");
-                return Ok(lessons.iter().filter(|&x| is_cached(x)).cloned().collect());
-            }
-        }
         let in_fetched_irval = |lsn: &Lesson| (from..=to).contains(&lsn.date_naive());
+        if let Some(lessons) = cached_tt.as_ref()
+            && cache_t.is_some_and(|t| cache::is_fresh("lesson", t))
+            && cache::Coverage::load(&self.userid, "lesson").covers((Some(from), Some(to)))
+        {
+            info!("lesson cache is fresh enough, using instead of refetching");
+            cache_t.inspect(|&t| cache::note_cached(t));
+            return Ok(lessons
+                .iter()
+                .filter(|&l| in_fetched_irval(l))
+                .cloned()
+                .collect());
+        }
         let remain_relevant = |lessons: &mut Vec<Lesson>| lessons.retain(in_fetched_irval);
         match self.fetch_vec((from, to)) {
             Ok(mut fetched_items) => {
+                cache::note_fetched();
                 let mut lessons = cached_tt.unwrap_or_default();
                 // delete cached if fresh was fetched for that period
                 lessons.retain(|cl| !in_fetched_irval(cl));
                 lessons.append(&mut fetched_items);
                 lessons.sort_unstable_by_key(|l| l.kezdet_idopont);
                 self.store_cache(&lessons)?;
+                self.store_coverage::<Lesson>((Some(from), Some(to)))?;
                 remain_relevant(&mut lessons);
                 Ok(lessons)
             }
             Err(e) => {
-                error!("only loading cached lessons, couldn't reach E-Kréta server: {e:?}");
-                eprintln!("only loading cached lessons, couldn't reach E-Kréta server: {e:?}");
+                unreachable_server("lesson", e)?;
                 let mut lessons = cached_tt.ok_or("nothing cached")?;
+                cache_t.inspect(|&t| cache::note_cached(t));
                 remain_relevant(&mut lessons);
-                // shouldn't have any lesson on weekends by default
-                if lessons.is_empty() && days_till_sun > TimeDelta::days(1) {
+                let coverage = cache::Coverage::load(&self.userid, "lesson");
+                // empty, as there are no lessons, or as it was never fetched?
+                if lessons.is_empty() && !coverage.covers((Some(from), Some(to))) {
                     Err("nothing cached for this period".into())
                 } else {
                     Ok(lessons)
@@ -285,6 +491,12 @@ impl User {
         }
     }
 
+    /// every [`Lesson`] cached so far, without reaching out to the server
+    pub fn get_cached_timetable(&self) -> Vec<Lesson> {
+        let (_, cached_tt) = self.load_cache::<Vec<Lesson>>().unzip();
+        cached_tt.unwrap_or_default()
+    }
+
     gen_get_for! { get_tests, Ancd, false,
         (|tests: &mut Vec<Ancd>| {
             tests.sort_unstable_by_key(|a| (a.datum, a.uid.clone()));
@@ -292,6 +504,25 @@ impl User {
         })
     }
 
+    gen_get_for! { get_homework, HomeWork, true,
+        (|homework: &mut Vec<HomeWork>| {
+            // fetched ones come last, they should replace cached ones, even if their deadline changed
+            homework.reverse();
+            homework.sort_by_key(|hw| hw.uid.clone());
+            homework.dedup_by_key(|hw| hw.uid.clone());
+            homework.sort_unstable_by_key(|hw| (hw.hatarido_datuma, hw.uid.clone()));
+        })
+    }
+
+    /// [`HomeWork`] marked done by the user, kept next to the cache, but never refetched
+    pub fn get_homework_done(&self) -> homework::HomeworkDone {
+        let (_, done) = self.load_cache().unzip();
+        done.unwrap_or_default()
+    }
+    pub fn store_homework_done(&self, done: &homework::HomeworkDone) -> Res<()> {
+        self.store_cache(done)
+    }
+
     gen_get_for! { get_absences, Absence, true,
         (|absences: &mut Vec<Absence>| {
             absences.sort_unstable_by_key(|a| (a.ora.kezdo_datum, !a.igazolt()));
@@ -302,19 +533,22 @@ impl User {
 
 /// [`Msg`]s and [`Attachment`]s
 impl User {
-    /// Download all [`Attachment`]s of this [`Msg`] to [`download_dir()`].
+    /// Download all [`Attachment`]s of this [`Msg`] to their own dir in [`download_dir()`].
     ///
     /// # Errors
     /// - net
     pub fn download_attachments(&self, msg: &MsgItem) -> Res<()> {
         for am in &msg.uzenet.csatolmanyok {
-            let download_to = messages::download_attachment_to(am);
+            let download_to = messages::download_attachment_to(msg, am);
             info!("downloading file://{}", download_to.display());
             // don't download if already exists
             if download_to.exists() {
                 debug!("not downloading, already done");
                 continue;
             }
+            if let Some(msg_dir) = download_to.parent() {
+                std::fs::create_dir_all(msg_dir)?;
+            }
             self.account
                 .download_attachment_to(am.azonosito, download_to, &self.headers()?)?;
 
@@ -351,11 +585,19 @@ impl User {
     }
 
     pub fn get_msg_oviews(&self) -> Res<Vec<MsgOview>> {
+        if let Some((cache_t, cached_msg_oviews)) = self.load_cache()
+            && cache::is_fresh("msgoview", cache_t)
+        {
+            info!("msgoview cache is fresh enough, not fetching");
+            cache::note_cached(cache_t);
+            return Ok(cached_msg_oviews);
+        }
         fn inner(usr: &User) -> Res<Vec<MsgOview>> {
             usr.account.fetch_msg_oviews(&usr.headers()?)
         } // used as a catcher of the `?` in the `usr.headers()?`, not to return too early on a `NO_NET=1`
         match inner(self) {
             Ok(mut msg_oviews) => {
+                cache::note_fetched();
                 msg_oviews.sort_unstable_by_key(|a| a.uzenet_kuldes_datum);
                 if !msg_oviews.is_empty() {
                     self.store_cache(&msg_oviews)?;
@@ -363,9 +605,9 @@ impl User {
                 Ok(msg_oviews)
             }
             Err(e) => {
-                error!("couldn't reach E-Kréta server: {e:?}, only loading cached messages");
-                eprintln!("couldn't reach E-Kréta server: {e:?}, only loading cached messages");
-                let (_t, cached_msg_oviews) = self.load_cache().ok_or("nothing cached")?;
+                unreachable_server("msgoview", e)?;
+                let (cache_t, cached_msg_oviews) = self.load_cache().ok_or("nothing cached")?;
+                cache::note_cached(cache_t);
                 Ok(cached_msg_oviews)
             }
         }
@@ -378,28 +620,67 @@ impl User {
         })
     }
 
-    /// load data from cache, fetch remaining(or full, depending on `fix_irval`) interval, merge these two sources
+    /// load data from cache, fetch remaining(or full, depending on `fix_irval`) interval, merge these two sources,
+    /// remaining is what's not yet settled as per [`cache::Coverage`], the interval actually fetched is returned as well
     /// # NOTE
     /// - if any of the two fails, it will be logged, but ignored and the other source will be used instead
     /// - don't forget to deduplicate the returned Vec **properly**
-    fn load_n_fetch<Ep>(&self, mut irval: OptIrval, fix_irval: bool) -> Res<Vec<Ep>>
+    fn load_n_fetch<Ep>(
+        &self,
+        mut irval: OptIrval,
+        fix_irval: bool,
+    ) -> Res<(Vec<Ep>, Option<OptIrval>)>
     where
         Ep: ekreta::Endpoint<Args = OptIrval> + for<'a> Deserialize<'a> + Clone,
     {
         let (cache_t, cached) = self.load_cache::<Vec<Ep>>().unzip();
         let orig_irval = irval;
+        let in_irval = |dt: LDateTime| {
+            orig_irval.0.is_none_or(|from| from <= dt.date_naive())
+                && orig_irval.1.is_none_or(|to| dt.date_naive() <= to)
+        };
+        let kind = utils::type_to_kind_name::<Ep>()?;
 
-        if fix_irval && cached.is_some() {
-            irval = utils::fix_from(cache_t, irval);
+        if let Some(cached) = cached.as_ref()
+            && cache_t.is_some_and(|t| cache::is_fresh(&kind, t))
+            && cache::Coverage::load(&self.userid, &kind).covers(irval)
+        {
+            info!("{kind} cache is fresh enough, not fetching");
+            cache_t.inspect(|&t| cache::note_cached(t));
+            let mut items = cached.clone();
+            items.retain(|item| item.when().is_none_or(in_irval));
+            return Ok((items, None));
         }
 
-        let fetched = self.fetch_vec::<Ep>(irval);
+        if fix_irval && cached.is_some() {
+            let coverage = cache::Coverage::load(&self.userid, &kind);
+            if let Some(settled) = coverage.settled_till(irval.0) {
+                info!("{kind} settled till {settled}, replacing {:?}", irval.0);
+                irval.0 = Some(settled);
+            }
+        }
+        let all_settled = irval
+            .0
+            .is_some_and(|from| irval.1.is_some_and(|to| to < from));
+
+        let fetched = if all_settled {
+            info!("everything asked for is cached, not fetching");
+            Ok(Vec::new())
+        } else {
+            self.fetch_vec::<Ep>(irval)
+        };
+        let fetched_irval = (fetched.is_ok() && !all_settled).then_some(irval);
+        let only_cached = fetched.is_err();
 
         let mut items = match fetched {
             Ok(fetched_items) => {
+                cache::note_fetched();
                 let mut cached = cached.unwrap_or_default();
-                let not_fetched =
-                    |dt: LDateTime| irval.0.is_none_or(|from| dt.date_naive() <= from);
+                // anything cached in the fetched interval is replaced, so deleted items don't stick around
+                let not_fetched = |dt: LDateTime| {
+                    irval.0.is_some_and(|from| dt.date_naive() < from)
+                        || irval.1.is_some_and(|to| to < dt.date_naive())
+                };
                 let orig_len = cached.len();
                 cached.retain(|item| item.when().is_none_or(not_fetched));
                 let overwritten = orig_len - cached.len();
@@ -407,20 +688,90 @@ impl User {
                 Ok([cached, fetched_items].concat())
             }
             Err(e) => {
-                let kind_name = utils::type_to_kind_name::<Ep>().unwrap_or_default();
-                error!("only loading cached {kind_name}, couldn't reach E-Kréta server: {e:?}");
-                eprintln!("only loading cached {kind_name}, couldn't reach E-Kréta server: {e:?}");
+                unreachable_server(&kind, e)?;
+                cache_t.inspect(|&t| cache::note_cached(t));
                 cached.ok_or("nothing cached".to_owned())
             }
         }?;
         let orig_len = items.len();
-        let in_irval = |dt: LDateTime| {
-            orig_irval.0.is_none_or(|from| from <= dt.date_naive())
-                && orig_irval.1.is_none_or(|to| dt.date_naive() <= to)
-        };
         items.retain(|item| item.when().is_none_or(in_irval));
         let deleted = orig_len - items.len();
         log::info!("deleted {deleted} items that weren't in interval asked");
-        Ok(items)
+        // empty, as there's nothing, or as it was never fetched?
+        if only_cached
+            && items.is_empty()
+            && !cache::Coverage::load(&self.userid, &kind).covers(orig_irval)
+        {
+            return Err(format!("no {kind} cached for this period").into());
+        }
+        Ok((items, fetched_irval))
+    }
+    /// record `irval` of `T` as fetched just now
+    fn store_coverage<T>(&self, irval: OptIrval) -> Res<()> {
+        let kind = utils::type_to_kind_name::<T>()?;
+        let mut coverage = cache::Coverage::load(&self.userid, &kind);
+        coverage.add(irval);
+        coverage.store(&self.userid, &kind)
+    }
+}
+
+/// error when `--offline`
+const OFFLINE: &str = "offline, not reaching out to the server";
+
+/// log `e`, and let the user know only cached `kind` is shown, unless it's what they asked for with `--offline`
+/// # Errors
+/// `e` itself, if something would need to be asked: cached data mustn't hide that from scripts
+fn unreachable_server(kind: &str, e: Box<dyn std::error::Error>) -> Res<()> {
+    if e.is::<args::NeedsInteraction>() {
+        return Err(e);
+    }
+    error!("only loading cached {kind}, couldn't reach E-Kréta server: {e:?}");
+    if !args::opts().offline {
+        eprintln!("only loading cached {kind}, couldn't reach E-Kréta server: {e:?}");
+    }
+    Ok(())
+}
+
+/// whether the server turned down the refresh token (eg. `invalid_grant`), rather than being unreachable
+///
+/// ekreta's errors aren't typed, so it goes by what they are: an answer that isn't a token, or a 4xx status
+fn refresh_rejected(e: &(dyn std::error::Error + 'static)) -> bool {
+    let mut source = Some(e);
+    while let Some(e) = source {
+        let msg = e.to_string().to_lowercase();
+        let status_4xx = msg.contains("status")
+            && msg
+                .split(|c: char| !c.is_ascii_digit())
+                .any(|code| code.len() == 3 && code.starts_with('4'));
+        if e.is::<serde_json::Error>() || msg.contains("invalid_grant") || status_4xx {
+            return true;
+        }
+        source = e.source();
+    }
+    false
+}
+
+/// run `cmd` with a shell, like git's credential helpers, the first line it prints is the password
+///
+/// `RSFILC_USERID` is set to `userid` for it
+fn password_from(cmd: &str, userid: &str) -> Res<String> {
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let output = std::process::Command::new(shell)
+        .args([flag, cmd])
+        .env("RSFILC_USERID", userid)
+        .stdin(std::process::Stdio::null())
+        .output()?;
+    if !output.status.success() {
+        return Err(format!("password command failed: {}", output.status).into());
     }
+    let stdout = String::from_utf8(output.stdout)?;
+    let password = stdout
+        .lines()
+        .next()
+        .ok_or("password command printed nothing")?;
+    Ok(password.to_string())
 }
@@ -1,20 +1,10 @@
-use ekreta::{OptIrval, Res};
+use ekreta::Res;
 use log::{debug, info};
+use serde_json::Value;
+use std::io::{ErrorKind, Write};
+use std::{path::Path, process::Command, process::Stdio};
 use yansi::Paint;
 
-/// use `cache_t` as `interval.0` (from) if some
-pub fn fix_from(cache_t: Option<ekreta::LDateTime>, mut irval: OptIrval) -> OptIrval {
-    debug!("got interval: {irval:?}");
-    if let Some(ct) = cache_t.map(|ct| ct.date_naive())
-        && irval
-            .0
-            .is_none_or(|from| from < ct && irval.1.is_none_or(|to| to > ct))
-    {
-        info!("from cached, replacing {:?} to {ct:?}", irval.0);
-        irval.0 = Some(ct);
-    }
-    irval
-}
 /// convert type name of `T` to a kind name, used for cache
 pub fn type_to_kind_name<T>() -> Res<String> {
     let type_name = std::any::type_name::<T>();
@@ -23,6 +13,78 @@ pub fn type_to_kind_name<T>() -> Res<String> {
     Ok(kind)
 }
 
+/// open `path` with the default application the system has for it
+pub fn open_externally(path: &Path) -> Res<()> {
+    info!("opening {} externally", path.display());
+    let mut opener = if cfg!(target_os = "windows") {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", "start", ""]);
+        cmd
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else {
+        Command::new("xdg-open")
+    };
+    let status = opener.arg(path).status()?;
+    if !status.success() {
+        return Err(format!("couldn't open {}: {status}", path.display()).into());
+    }
+    Ok(())
+}
+
+/// run `cmd` (split on whitespace), write `input` to its stdin and return what it printed
+pub fn pipe_through(cmd: &str, input: &str) -> Res<String> {
+    let mut words = cmd.split_whitespace();
+    let program = words.next().ok_or("empty command")?;
+    debug!("piping through {cmd:?}");
+    let mut child = Command::new(program)
+        .args(words)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let mut stdin = child.stdin.take().ok_or("couldn't open stdin")?;
+    // written from another thread, as `child` may block on a full stdout before reading all of it
+    let (output, written) = std::thread::scope(|s| {
+        // stdin gets dropped right after writing, so `child` knows the input is over
+        let writer = s.spawn(move || stdin.write_all(input.as_bytes()));
+        (child.wait_with_output(), writer.join())
+    });
+    let output = output?;
+    if !output.status.success() {
+        return Err(format!("{cmd:?} failed: {}", output.status).into());
+    }
+    written.map_err(|_| "writing to stdin panicked")??;
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// print `text`, through `$PAGER` (`less -R` by default) if it wouldn't fit on the terminal
+pub fn print_paged(text: &str) -> Res<()> {
+    let fits = termize::dimensions_stdout().is_none_or(|(_w, h)| text.lines().count() < h);
+    let pager = std::env::var("PAGER").unwrap_or_else(|_| String::from("less -R"));
+    let mut words = pager.split_whitespace();
+    let Some(program) = words.next().filter(|_| !fits) else {
+        println!("{text}");
+        return Ok(());
+    };
+    let spawned = Command::new(program)
+        .args(words)
+        .stdin(Stdio::piped())
+        .spawn();
+    let Ok(mut child) = spawned.inspect_err(|e| log::warn!("couldn't start {pager:?}: {e}")) else {
+        println!("{text}");
+        return Ok(());
+    };
+    if let Some(mut stdin) = child.stdin.take()
+        && let Err(e) = writeln!(stdin, "{text}")
+        && e.kind() != ErrorKind::BrokenPipe
+    {
+        return Err(e.into()); // quitting the pager early is fine
+    }
+    child.wait()?;
+    Ok(())
+}
+
 #[macro_export]
 /// generate get fn named `fn_name` for type `ep`, specify whether
 /// content once `cached_can_change` or not then sort with `sorting`
@@ -33,10 +95,13 @@ macro_rules! gen_get_for {
         /// net
         pub fn $fn_name(&self, interval: OptIrval) -> Res<Vec<$ep>> {
             self.load_n_fetch::<$ep>(interval.clone(), !$cached_can_change)
-                .map(|mut items| {
+                .map(|(mut items, fetched)| {
                     $sorting(&mut items);
                     if interval.0.is_none() {
                         self.store_cache(&items)?;
+                        if let Some(fetched) = fetched {
+                            self.store_coverage::<$ep>(fetched)?;
+                        }
                     }
                     Ok(items)
                 })?
@@ -44,6 +109,25 @@ macro_rules! gen_get_for {
     };
 }
 
+/// print `data` as json, with `cached_at` added to it, see [`add_cached_at`] and [`crate::cache::data_age`]
+pub fn print_json<T: serde::Serialize>(data: &T) -> Res<()> {
+    let mut json = serde_json::to_value(data)?;
+    if let Some((cached_at, _)) = crate::cache::data_age() {
+        add_cached_at(&mut json, &serde_json::to_value(cached_at)?);
+    }
+    println!("{json}");
+    Ok(())
+}
+/// add `cached_at` to `val` if it's an object, or to the objects in it if an array,
+/// even in nested ones, eg. `(id, item)` rows
+fn add_cached_at(val: &mut Value, cached_at: &Value) {
+    match val {
+        Value::Object(obj) => _ = obj.insert("cached_at".into(), cached_at.clone()),
+        Value::Array(items) => items.iter_mut().for_each(|v| add_cached_at(v, cached_at)),
+        _ => {}
+    }
+}
+
 /// print table with headers
 pub fn print_table_wh<I: IntoIterator<Item: ToString>>(headers: I, data: Vec<Vec<String>>) {
     let mut tbl = ascii_table::AsciiTable::default();
@@ -78,8 +162,7 @@ where
         let data = iter.take(num).map(to_str).collect::<Vec<_>>();
         print_table_wh(headers, data);
     } else {
-        let data = serde_json::to_string(&iter.take(num).collect::<Vec<_>>())?;
-        println!("{data}");
+        print_json(&iter.take(num).collect::<Vec<_>>())?;
     }
     Ok(())
 }